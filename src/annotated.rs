@@ -0,0 +1,163 @@
+use kuchiki::{Node, NodeRef};
+use options::DiffOptions;
+use {get_children, push_path_segment, walk_roots, walk_sides, Side, Walker};
+use {Difference, ToOutput};
+
+use std::collections::HashMap;
+use std::ptr;
+
+/// How a node of an `AnnotatedTree` compares with the other side.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Annotation {
+    /// The node is the same on both sides (its children are annotated separately).
+    Matched,
+    /// The node is only in the first content.
+    OnlyLeft,
+    /// The node is only in the second content.
+    OnlyRight,
+    /// The node differs. Its children are only compared (and annotated) if the difference
+    /// doesn't prevent it, like a `Difference::AttributeOrder`.
+    Changed(Box<Difference>),
+}
+
+/// A node of the tree merging two contents (see `annotated_diff`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotatedTree {
+    /// The tag name of the node (of the first content, except for `OnlyRight`), empty for text
+    /// nodes and for the document itself.
+    pub element_name: String,
+    /// The path of the node in the first content (with the segments of
+    /// `ElementInformation::path`). The text nodes and the nodes only in the second content
+    /// have the path of their parent.
+    pub path: String,
+    pub annotation: Annotation,
+    /// The children of the node which were compared.
+    pub children: Vec<AnnotatedTree>,
+}
+
+// Identifies a pair of nodes, `None` being a missing one.
+type PairKey = (*const Node, *const Node);
+
+fn pair_key(nodes: (Option<&NodeRef>, Option<&NodeRef>)) -> PairKey {
+    let key = |node: Option<&NodeRef>| node.map_or(ptr::null(), |node| &*node.0 as *const Node);
+    (key(nodes.0), key(nodes.1))
+}
+
+// The children of a matching node whose subtree was matched as a whole (see
+// `DiffOptions::unordered`).
+fn matched_children(node: &NodeRef, path: &mut Vec<String>,
+                    options: &DiffOptions) -> Vec<AnnotatedTree> {
+    let mut pos = HashMap::new();
+    get_children(node, options).into_iter().map(|child| {
+        let need_pop = push_path_segment(&child, &mut pos, path);
+        let tree = AnnotatedTree {
+            element_name: child.name(),
+            path: path.join("/"),
+            annotation: Annotation::Matched,
+            children: matched_children(&child, path, options),
+        };
+        if need_pop {
+            path.pop();
+        }
+        tree
+    }).collect()
+}
+
+/// Same as `annotated_diff` but the comparison is configured by `options`.
+///
+/// `max_depth` and `max_differences` are ignored since the whole tree is annotated. The nodes
+/// whose difference isn't reported (see `DiffOptions::min_subtree_size`) are `Matched` if they
+/// are on both sides.
+pub fn annotated_diff_with_options(content1: &str, content2: &str,
+                                   options: &DiffOptions) -> AnnotatedTree {
+    let mut options = options.clone();
+    options.max_depth = None;
+    options.max_differences = None;
+    let (side1, side2) = (Side::parse(content1, &options), Side::parse(content2, &options));
+    let (root1, root2) = walk_roots(&side1, &side2, &options);
+    // The nodes gone through, with the position of their parent (the root being the first
+    // one), the pair they come from and their node in the first content.
+    let mut nodes = vec![(AnnotatedTree {
+        element_name: root1.name(),
+        path: String::new(),
+        annotation: Annotation::Matched,
+        children: Vec::new(),
+    }, 0, pair_key((Some(&root1), Some(&root2))), Some(root1.clone()))];
+    let mut differences = HashMap::new();
+    {
+        let mut positions = HashMap::new();
+        positions.insert(nodes[0].2, 0);
+        let mut steps = |pair: (Option<&NodeRef>, Option<&NodeRef>),
+                         parents: (&NodeRef, &NodeRef), path: &str| {
+            let parent = positions.get(&pair_key((Some(parents.0), Some(parents.1))))
+                                  .cloned()
+                                  .unwrap_or(0);
+            let annotation = match pair {
+                (Some(_), Some(_)) => Annotation::Matched,
+                (Some(_), None) => Annotation::OnlyLeft,
+                _ => Annotation::OnlyRight,
+            };
+            positions.insert(pair_key(pair), nodes.len());
+            nodes.push((AnnotatedTree {
+                element_name: pair.0.or(pair.1).map(|node| node.name()).unwrap_or_default(),
+                path: path.to_owned(),
+                annotation,
+                children: Vec::new(),
+            }, parent, pair_key(pair), pair.0.cloned()));
+        };
+        let mut add = |diff, node1: Option<&NodeRef>, node2: Option<&NodeRef>| {
+            differences.entry(pair_key((node1, node2))).or_insert(diff);
+            true
+        };
+        let mut walker = Walker::new(&options, &mut add);
+        walker.steps = Some(&mut steps);
+        walk_sides(&side1, &side2, &mut walker);
+    }
+    // The children come after their parent, so they are moved into it from the last node.
+    let mut children = vec![Vec::new(); nodes.len()];
+    while let Some((mut tree, parent, key, node1)) = nodes.pop() {
+        match differences.remove(&key) {
+            Some(Difference::NotPresent { elem: Some(_), .. }) => {
+                tree.annotation = Annotation::OnlyLeft;
+            }
+            Some(Difference::NotPresent { elem: None, .. }) => {
+                tree.annotation = Annotation::OnlyRight;
+            }
+            Some(diff) => tree.annotation = Annotation::Changed(Box::new(diff)),
+            None => {}
+        }
+        let mut tree_children = children.pop().unwrap_or_default();
+        tree_children.reverse();
+        let whole_match = tree_children.is_empty() && tree.annotation == Annotation::Matched;
+        tree.children = match node1 {
+            Some(ref node1) if whole_match => {
+                let mut path = tree.path.split('/').map(|s| s.to_owned()).collect::<Vec<_>>();
+                matched_children(node1, &mut path, &options)
+            }
+            _ => tree_children,
+        };
+        if nodes.is_empty() {
+            return tree
+        }
+        children[parent].push(tree);
+    }
+    unreachable!()
+}
+
+/// Merges the two html content strings into one tree in which each node says if it matches
+/// the other side, is only present on one side or differs.
+///
+/// The nodes are matched the same way as in `get_differences`, so each `Changed`, `OnlyLeft`
+/// and `OnlyRight` node corresponds to one of the differences it returns.
+///
+/// ```
+/// use html_diff::{annotated_diff, Annotation};
+///
+/// let tree = annotated_diff("<p>a</p>", "<p>a</p><p>b</p>");
+/// let body = &tree.children[0].children[1];
+/// assert_eq!(body.children[0].annotation, Annotation::Matched);
+/// assert_eq!(body.children[1].annotation, Annotation::OnlyRight);
+/// ```
+pub fn annotated_diff(content1: &str, content2: &str) -> AnnotatedTree {
+    annotated_diff_with_options(content1, content2, &DiffOptions::default())
+}
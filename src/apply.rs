@@ -0,0 +1,184 @@
+use kuchiki::{ElementData, NodeData, NodeRef};
+
+use std::error::Error;
+use std::fmt;
+
+use {attribute_name, serialize_sorted, Difference, DifferenceKind, ElementInformation};
+
+/// The errors returned by `apply_differences`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApplyError {
+    /// No element was found at the given path.
+    PathNotFound(String),
+    /// The node a difference is about wasn't found in the element at the given path.
+    NodeNotFound(String),
+    /// This kind of difference can't be applied.
+    Unsupported(DifferenceKind),
+}
+
+impl fmt::Display for ApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ApplyError::PathNotFound(ref path) => write!(f, "no element at \"{}\"", path),
+            ApplyError::NodeNotFound(ref path) => write!(f, "node not found in \"{}\"", path),
+            ApplyError::Unsupported(kind) => {
+                write!(f, "{:?} differences can't be applied", kind)
+            }
+        }
+    }
+}
+
+impl Error for ApplyError {}
+
+// Copies `node` and its whole subtree (including the content of the templates).
+fn deep_clone(node: &NodeRef) -> NodeRef {
+    let copy = match *node.data() {
+        NodeData::Element(ref elem) => {
+            NodeRef::new(NodeData::Element(ElementData {
+                name: elem.name.clone(),
+                attributes: elem.attributes.clone(),
+                template_contents: elem.template_contents.as_ref().map(deep_clone),
+            }))
+        }
+        ref data => NodeRef::new(data.clone()),
+    };
+    for child in node.children() {
+        copy.append(deep_clone(&child));
+    }
+    copy
+}
+
+fn children(node: &NodeRef) -> impl Iterator<Item = NodeRef> {
+    let template_contents = node.as_element().and_then(|e| e.template_contents.clone());
+    template_contents.unwrap_or_else(|| node.clone()).children()
+}
+
+// Finds the element at `path` (`/html[0]/body[0]`) below `root`.
+fn find_path(root: &NodeRef, path: &str) -> Result<NodeRef, ApplyError> {
+    let not_found = || ApplyError::PathNotFound(path.to_owned());
+    let mut node = root.clone();
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        let pos = segment.find('[').ok_or_else(not_found)?;
+        let name = &segment[..pos];
+        let index = segment[pos + 1..].trim_end_matches(']').parse::<usize>()
+                                      .map_err(|_| not_found())?;
+        node = children(&node).filter(|c| {
+                                  c.as_element().map(|e| &*e.name.local == name).unwrap_or(false)
+                              })
+                              .nth(index)
+                              .ok_or_else(not_found)?;
+    }
+    Ok(node)
+}
+
+// Finds the child element of the element at `elem.path` which serializes to
+// `elem.element_content`.
+fn find_element(root: &NodeRef, elem: &ElementInformation) -> Result<NodeRef, ApplyError> {
+    children(&find_path(root, &elem.path)?)
+        .find(|c| {
+            c.as_element().map(|e| *e.name.local == *elem.element_name).unwrap_or(false) &&
+            serialize_sorted(c) == elem.element_content
+        })
+        .ok_or_else(|| ApplyError::NodeNotFound(elem.path.clone()))
+}
+
+// The runs of text nodes of `parent` between its elements, which are compared as one text by
+// default (see `DiffOptions::collapse_text_nodes`): the comments in a run are ignored.
+fn text_runs(parent: &NodeRef) -> Vec<Vec<NodeRef>> {
+    let mut runs = vec![Vec::new()];
+    for child in children(parent) {
+        if child.as_element().is_some() {
+            runs.push(Vec::new());
+        } else if child.as_text().is_some() {
+            if let Some(run) = runs.last_mut() {
+                run.push(child);
+            }
+        }
+    }
+    runs.retain(|run| !run.is_empty());
+    runs
+}
+
+fn text_of(nodes: &[NodeRef]) -> String {
+    nodes.iter().filter_map(|node| node.as_text().map(|text| text.borrow().clone())).collect()
+}
+
+fn apply_difference(root: &NodeRef, difference: &Difference) -> Result<(), ApplyError> {
+    match *difference {
+        Difference::NodeText { ref elem, ref elem_text, ref opposite_elem_text, .. } => {
+            let parent = find_path(root, &elem.path)?;
+            let runs = text_runs(&parent);
+            // A whole run, or one of its nodes if they were compared separately.
+            let nodes = runs.iter()
+                            .find(|run| text_of(run) == *elem_text)
+                            .map(|run| &run[..])
+                            .or_else(|| {
+                                runs.iter().flat_map(|run| run.chunks(1))
+                                    .find(|node| text_of(node) == *elem_text)
+                            })
+                            .ok_or_else(|| ApplyError::NodeNotFound(elem.path.clone()))?;
+            if let Some(text) = nodes[0].as_text() {
+                *text.borrow_mut() = opposite_elem_text.clone();
+            }
+            for node in &nodes[1..] {
+                node.detach();
+            }
+            Ok(())
+        }
+        Difference::NodeAttributes { ref elem, ref elem_attributes,
+                                     ref opposite_elem_attributes, .. } => {
+            let node = find_element(root, elem)?;
+            let element = node.as_element().expect("find_element returns elements");
+            let mut attributes = element.attributes.borrow_mut();
+            for name in elem_attributes.keys() {
+                if !opposite_elem_attributes.contains_key(name) {
+                    attributes.map.retain(|k, _| {
+                        attribute_name(k.prefix.as_deref(), &k.local) != *name
+                    });
+                }
+            }
+            for (name, value) in opposite_elem_attributes {
+                if elem_attributes.get(name) == Some(value) {
+                    continue
+                }
+                let existing = attributes.map.iter_mut().find(|attribute| {
+                    attribute_name(attribute.0.prefix.as_deref(), &attribute.0.local) == *name
+                });
+                match existing {
+                    Some((_, existing)) => *existing = value.clone(),
+                    None => {
+                        attributes.insert(name.as_str(), value.clone());
+                    }
+                }
+            }
+            Ok(())
+        }
+        ref difference => Err(ApplyError::Unsupported(difference.kind())),
+    }
+}
+
+/// Applies the differences found between two documents (with `get_differences_against` for
+/// example) to a copy of the first one, so it becomes like the second one. The given document
+/// isn't modified.
+///
+/// Only `Difference::NodeText` and `Difference::NodeAttributes` can be applied for now, the
+/// other kinds return `ApplyError::Unsupported`. The differences have to be found with the
+/// paths unchanged (so without `DiffOptions::auto_unwrap`, `DiffOptions::normalize_tables` or
+/// `DiffOptions::max_content_len` for example).
+///
+/// ```
+/// use html_diff::{apply_differences, get_differences_against, parse_document};
+///
+/// let document = parse_document("<p class=\"a\">a</p><p>old</p>");
+/// let differences = get_differences_against(&document, "<p class=\"b\">a</p><p>new</p>");
+/// let patched = apply_differences(&document, &differences).unwrap();
+/// assert!(get_differences_against(&patched, "<p class=\"b\">a</p><p>new</p>").is_empty());
+/// ```
+pub fn apply_differences(document: &NodeRef,
+                         differences: &[Difference]) -> Result<NodeRef, ApplyError> {
+    let copy = deep_clone(document);
+    for difference in differences {
+        apply_difference(&copy, difference)?;
+    }
+    Ok(copy)
+}
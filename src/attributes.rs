@@ -0,0 +1,177 @@
+use json;
+use options::DiffOptions;
+use urls;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+fn has_prefix(name: &str, prefixes: &[String]) -> bool {
+    prefixes.iter().any(|p| name.starts_with(p.as_str()))
+}
+
+/// Returns `true` if the `name` attribute mustn't be compared.
+pub fn is_ignored(name: &str, options: &DiffOptions) -> bool {
+    if has_prefix(name, &options.ignored_attribute_prefixes) {
+        return true
+    }
+    if options.only_attribute_prefixes.is_empty() && options.only_compare_attributes.is_empty() {
+        return false
+    }
+    !has_prefix(name, &options.only_attribute_prefixes) &&
+    !options.only_compare_attributes.iter().any(|a| a == name)
+}
+
+/// Returns `true` if the two values of the `name` attribute are considered equal.
+pub fn values_match(name: &str, value1: &str, value2: &str, options: &DiffOptions) -> bool {
+    let (value1, value2) = if options.trim_attribute_values {
+        (value1.trim(), value2.trim())
+    } else {
+        (value1, value2)
+    };
+    if value1 == value2 {
+        return true
+    }
+    if options.json_attributes.iter().any(|a| a == name) {
+        if let (Some(value1), Some(value2)) = (json::parse(value1), json::parse(value2)) {
+            return value1 == value2
+        }
+    }
+    if options.class_as_set && name == "class" {
+        return value1.split_whitespace().collect::<BTreeSet<_>>() ==
+               value2.split_whitespace().collect::<BTreeSet<_>>()
+    }
+    if options.style_as_declarations && name == "style" {
+        return style_declarations(value1) == style_declarations(value2)
+    }
+    if options.numeric_list_attributes.iter().any(|a| a == name) {
+        return numeric_lists_match(value1, value2, options.numeric_tolerance)
+    }
+    let (value1, value2) = if options.stripped_attributes.iter().any(|a| a == name) {
+        (strip_affixes(value1, options), strip_affixes(value2, options))
+    } else {
+        (value1, value2)
+    };
+    let (value1, value2) = match options.url_bases {
+        Some((ref base1, ref base2)) if urls::is_url_attribute(name) => {
+            (urls::resolve(base1, value1).unwrap_or_else(|| value1.to_owned()),
+             urls::resolve(base2, value2).unwrap_or_else(|| value2.to_owned()))
+        }
+        _ => (value1.to_owned(), value2.to_owned()),
+    };
+    if value1 == value2 {
+        return true
+    }
+    if options.decode_percent_encoding.iter().any(|a| a == name) {
+        if let (Some(value1), Some(value2)) = (percent_decode(&value1), percent_decode(&value2)) {
+            return value1 == value2
+        }
+    }
+    false
+}
+
+/// Returns the value of the last declaration of `property` in the `style` attribute value
+/// `style` (`"none"` for `display` in `"color: red; display: none"`), lowercased and without
+/// `!important`.
+pub fn style_property(style: &str, property: &str) -> Option<String> {
+    style.rsplit(';')
+         .filter_map(|declaration| {
+             let mut parts = declaration.splitn(2, ':');
+             match (parts.next(), parts.next()) {
+                 (Some(name), Some(value)) if name.trim().eq_ignore_ascii_case(property) => {
+                     let value = value.trim().to_lowercase();
+                     Some(value.trim_end_matches("!important").trim_end().to_owned())
+                 }
+                 _ => None,
+             }
+         })
+         .next()
+}
+
+// Compares two lists of numbers separated by whitespace or commas.
+fn numeric_lists_match(value1: &str, value2: &str, tolerance: f64) -> bool {
+    let tokens = |value: &str| {
+        value.split(|c: char| c == ',' || c.is_whitespace())
+             .filter(|t| !t.is_empty())
+             .map(|t| t.to_owned())
+             .collect::<Vec<_>>()
+    };
+    let (tokens1, tokens2) = (tokens(value1), tokens(value2));
+    tokens1.len() == tokens2.len() &&
+    tokens1.iter().zip(tokens2.iter()).all(|(token1, token2)| {
+        match (token1.parse::<f64>(), token2.parse::<f64>()) {
+            (Ok(number1), Ok(number2)) => (number1 - number2).abs() <= tolerance,
+            _ => token1 == token2,
+        }
+    })
+}
+
+// The declarations of a `style` attribute value, by lowercased property name.
+fn style_declarations(style: &str) -> BTreeMap<String, String> {
+    style.split(';')
+         .filter_map(|declaration| {
+             let mut parts = declaration.splitn(2, ':');
+             match (parts.next(), parts.next()) {
+                 (Some(name), Some(value)) => {
+                     Some((name.trim().to_lowercase(), collapse_whitespace(value)))
+                 }
+                 _ => None,
+             }
+         })
+         .collect()
+}
+
+/// Trims the value and replaces every whitespace sequence with a single space.
+pub fn collapse_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Removes the first matching `DiffOptions::stripped_prefixes` and
+// `DiffOptions::stripped_suffixes` from `value`.
+fn strip_affixes<'a>(value: &'a str, options: &DiffOptions) -> &'a str {
+    let mut value = options.stripped_prefixes.iter()
+                                             .filter_map(|p| value.strip_prefix(p.as_str()))
+                                             .next()
+                                             .unwrap_or(value);
+    for suffix in &options.stripped_suffixes {
+        if suffix == "?" {
+            if let Some(pos) = value.find(&['?', '#'][..]) {
+                value = &value[..pos];
+                break
+            }
+        } else if let Some(stripped) = value.strip_suffix(suffix.as_str()) {
+            value = stripped;
+            break
+        }
+    }
+    value
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Returns `None` if `s` contains an invalid percent sequence or doesn't decode to UTF-8.
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut pos = 0;
+    while pos < bytes.len() {
+        if bytes[pos] == b'%' {
+            if pos + 2 >= bytes.len() {
+                return None
+            }
+            let high = hex_value(bytes[pos + 1])?;
+            let low = hex_value(bytes[pos + 2])?;
+            decoded.push(high * 16 + low);
+            pos += 3;
+        } else {
+            decoded.push(bytes[pos]);
+            pos += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
@@ -0,0 +1,59 @@
+use {get_differences_with_options, Difference, DiffOptions, ElementInformation};
+
+/// The differences between two documents sorted by side (see `bidirectional_diff`).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BiDiff {
+    /// The elements only present in the first document.
+    pub only_in_left: Vec<ElementInformation>,
+    /// The elements only present in the second document.
+    pub only_in_right: Vec<ElementInformation>,
+    /// The other differences, found between nodes present on both sides.
+    pub changed: Vec<Difference>,
+}
+
+impl BiDiff {
+    /// Sorts `differences` by side, keeping their order within each side.
+    pub fn from_differences(differences: Vec<Difference>) -> BiDiff {
+        let mut bidiff = BiDiff::default();
+        for diff in differences {
+            match diff {
+                Difference::NotPresent { elem: Some(elem), opposite_elem: None } => {
+                    bidiff.only_in_left.push(elem)
+                }
+                Difference::NotPresent { elem: None, opposite_elem: Some(elem) } => {
+                    bidiff.only_in_right.push(elem)
+                }
+                diff => bidiff.changed.push(diff),
+            }
+        }
+        bidiff
+    }
+
+    /// Returns `true` if no difference was found.
+    pub fn is_empty(&self) -> bool {
+        self.only_in_left.is_empty() && self.only_in_right.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares the two html content strings like `get_differences` but sorts the differences by
+/// side: the elements only in `content1`, the ones only in `content2` and the ones changed
+/// between them.
+///
+/// ```
+/// use html_diff::bidirectional_diff;
+///
+/// let bidiff = bidirectional_diff("<p>a</p><p>b</p>", "<p>c</p>");
+/// assert_eq!(bidiff.only_in_left.len(), 1);
+/// assert_eq!(bidiff.only_in_left[0].element_name, "p");
+/// assert!(bidiff.only_in_right.is_empty());
+/// assert_eq!(bidiff.changed.len(), 1);
+/// ```
+pub fn bidirectional_diff(content1: &str, content2: &str) -> BiDiff {
+    bidirectional_diff_with_options(content1, content2, &DiffOptions::default())
+}
+
+/// Same as `bidirectional_diff` but the comparison is configured by `options`.
+pub fn bidirectional_diff_with_options(content1: &str, content2: &str,
+                                       options: &DiffOptions) -> BiDiff {
+    BiDiff::from_differences(get_differences_with_options(content1, content2, options))
+}
@@ -0,0 +1,74 @@
+use kuchiki::{NodeData, NodeRef};
+
+use options::DiffOptions;
+use {element_attributes, get_children, text};
+
+fn escape(s: &str, attribute: bool) -> String {
+    let s = s.replace('&', "&amp;");
+    if attribute {
+        s.replace('"', "&quot;")
+    } else {
+        s.replace('<', "&lt;").replace('>', "&gt;")
+    }
+}
+
+fn write_node(node: &NodeRef, options: &DiffOptions, out: &mut String) {
+    if let Some(elem) = node.clone().into_element_ref() {
+        let mut attributes = element_attributes(&elem, options).into_iter().collect::<Vec<_>>();
+        attributes.sort();
+        out.push_str(&format!("<{}", elem.name.local));
+        for (name, value) in attributes {
+            out.push_str(&format!(" {}=\"{}\"", name, escape(&value, true)));
+        }
+        out.push('>');
+        write_children(node, options, out);
+        out.push_str(&format!("</{}>", elem.name.local));
+    } else if let Some(text) = node.as_text() {
+        out.push_str(&escape(&text::normalize(&text.borrow(), options), false));
+    } else if let NodeData::Doctype(ref doctype) = *node.data() {
+        out.push_str(&format!("<!DOCTYPE {}", doctype.name.to_lowercase()));
+        for id in &[&doctype.public_id, &doctype.system_id] {
+            if !id.is_empty() {
+                out.push_str(&format!(" \"{}\"", id));
+            }
+        }
+        out.push('>');
+    }
+}
+
+fn write_children(node: &NodeRef, options: &DiffOptions, out: &mut String) {
+    for child in get_children(node, options) {
+        write_node(&child, options, out);
+    }
+}
+
+/// Returns the canonical form of the html content string, as compared with `options`: two
+/// contents with the same canonical form have no differences.
+///
+/// The output reflects:
+///
+///  * the comments, processing instructions and whitespace-only texts being dropped;
+///  * the text normalizations (`DiffOptions::normalize_whitespace`,
+///    `DiffOptions::case_insensitive_text`, `DiffOptions::treat_nbsp_as_space` and
+///    `DiffOptions::collapse_text_nodes`);
+///  * the attributes sorted by name, without the ignored ones, and with the changes of
+///    `DiffOptions::normalize_dom` and `DiffOptions::form_semantics`;
+///  * the table sections of `DiffOptions::normalize_tables`.
+///
+/// Every element gets a closing tag. The options matching values or nodes without changing
+/// them (like `DiffOptions::decode_percent_encoding`, `DiffOptions::json_attributes`,
+/// `DiffOptions::unordered` or `DiffOptions::min_text_len`) aren't reflected, so two contents
+/// without differences can still have different canonical forms with them.
+///
+/// ```
+/// use html_diff::{normalize, DiffOptions};
+///
+/// let options = DiffOptions::default().normalize_whitespace(true);
+/// assert_eq!(normalize("<p id=\"a\" class=\"b\">  a\n b <!-- c --></p>", &options),
+///            "<html><head></head><body><p class=\"b\" id=\"a\">a b</p></body></html>");
+/// ```
+pub fn normalize(content: &str, options: &DiffOptions) -> String {
+    let mut out = String::new();
+    write_children(&::parse_document(content), options, &mut out);
+    out
+}
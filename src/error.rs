@@ -0,0 +1,55 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::string::FromUtf8Error;
+
+/// The errors returned by the fallible functions of the crate.
+#[derive(Debug)]
+pub enum DiffError {
+    /// An input couldn't be read.
+    Io(io::Error),
+    /// An input isn't valid UTF-8.
+    Utf8(FromUtf8Error),
+    /// A CSS selector couldn't be parsed.
+    SelectorParse(String),
+    /// An input is empty.
+    EmptyInput,
+}
+
+impl fmt::Display for DiffError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DiffError::Io(ref err) => write!(f, "{}", err),
+            DiffError::Utf8(ref err) => {
+                write!(f, "not valid UTF-8 (invalid byte at offset {})",
+                       err.utf8_error().valid_up_to())
+            }
+            DiffError::SelectorParse(ref selector) => {
+                write!(f, "invalid CSS selector \"{}\"", selector)
+            }
+            DiffError::EmptyInput => write!(f, "empty input"),
+        }
+    }
+}
+
+impl Error for DiffError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            DiffError::Io(ref err) => Some(err),
+            DiffError::Utf8(ref err) => Some(err),
+            DiffError::SelectorParse(_) | DiffError::EmptyInput => None,
+        }
+    }
+}
+
+impl From<io::Error> for DiffError {
+    fn from(err: io::Error) -> DiffError {
+        DiffError::Io(err)
+    }
+}
+
+impl From<FromUtf8Error> for DiffError {
+    fn from(err: FromUtf8Error) -> DiffError {
+        DiffError::Utf8(err)
+    }
+}
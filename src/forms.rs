@@ -0,0 +1,73 @@
+use attributes;
+use kuchiki::NodeRef;
+
+use std::collections::HashMap;
+
+// The attributes whose value doesn't matter, only their presence.
+const BOOLEAN_ATTRIBUTES: &[&str] = &["checked", "selected", "disabled", "readonly", "multiple",
+                                      "required", "autofocus"];
+
+fn is_element(node: &NodeRef, name: &str) -> bool {
+    node.as_element().map(|e| &*e.name.local == name).unwrap_or(false)
+}
+
+fn has_attribute(node: &NodeRef, name: &str) -> bool {
+    node.as_element().map(|e| e.attributes.borrow().contains(name)).unwrap_or(false)
+}
+
+// The `<select>` containing the option, directly or through an `<optgroup>`.
+fn parent_select(option: &NodeRef) -> Option<NodeRef> {
+    let parent = option.parent()?;
+    if is_element(&parent, "optgroup") {
+        parent.parent().filter(|p| is_element(p, "select"))
+    } else if is_element(&parent, "select") {
+        Some(parent)
+    } else {
+        None
+    }
+}
+
+// The option a browser shows as selected in a single-choice `<select>`: the last one with the
+// `selected` attribute or, if there is none, the first one that isn't disabled.
+fn selected_option(select: &NodeRef) -> Option<NodeRef> {
+    let options = select.descendants().filter(|n| is_element(n, "option")).collect::<Vec<_>>();
+    options.iter()
+           .rev()
+           .find(|o| has_attribute(o, "selected"))
+           .or_else(|| options.iter().find(|o| !has_attribute(o, "disabled")))
+           .cloned()
+}
+
+/// Replaces the attributes of the `name` element with the state they give to the form control:
+/// boolean attributes are only compared by presence, `<input>` has a `text` type by default,
+/// `<option>` takes its text as value by default and only the option a single-choice `<select>`
+/// displays is `selected`.
+pub fn apply_semantics(node: &NodeRef, name: &str, attributes: &mut HashMap<String, String>) {
+    for attribute in BOOLEAN_ATTRIBUTES {
+        if let Some(value) = attributes.get_mut(*attribute) {
+            value.clear();
+        }
+    }
+    match name {
+        "input" => {
+            let kind = attributes.remove("type").unwrap_or_else(|| "text".to_owned());
+            attributes.insert("type".to_owned(), kind.to_lowercase());
+        }
+        "option" => {
+            if !attributes.contains_key("value") {
+                attributes.insert("value".to_owned(),
+                                  attributes::collapse_whitespace(&node.text_contents()));
+            }
+            if let Some(select) = parent_select(node) {
+                if !has_attribute(&select, "multiple") {
+                    if selected_option(&select).as_ref() == Some(node) {
+                        attributes.insert("selected".to_owned(), String::new());
+                    } else {
+                        attributes.remove("selected");
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
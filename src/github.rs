@@ -0,0 +1,120 @@
+use kuchiki::{Node, NodeRef};
+use options::DiffOptions;
+use source_tags::{line_column, SourceTags};
+use {walk_roots, walk_sides, Difference, Side, Walker};
+
+use std::collections::HashMap;
+
+// Returns the differences between the two html content strings, each with the line and the
+// column in `content2` of the start tag of the element it was found on: the element of
+// `content2`, the one in which the node of `content1` is missing, or the closest ancestor
+// with a start tag in the source. The differences without a known position are at `1:1`.
+fn located_differences(content1: &str, content2: &str,
+                       options: &DiffOptions) -> Vec<(Difference, (usize, usize))> {
+    let (side1, side2) = (Side::parse(content1, options), Side::parse(content2, options));
+    let (root1, root2) = walk_roots(&side1, &side2, options);
+    let key = |node: &NodeRef| &*node.0 as *const Node;
+    // The start tags are scanned in the content parsed for the comparison.
+    let (source, wrapper_len) = if options.xml_syntax {
+        (format!("<svg>{}</svg>", content2), "<svg>".len())
+    } else {
+        (content2.to_owned(), 0)
+    };
+    let tags = SourceTags::new(&source, &side2.root);
+    let mut differences = Vec::new();
+    // The nodes of `content2` matching the ones of `content1`.
+    let mut matching = HashMap::new();
+    matching.insert(key(&root1), root2);
+    {
+        let mut steps = |pair: (Option<&NodeRef>, Option<&NodeRef>), _: (&NodeRef, &NodeRef),
+                         _: &str| {
+            if let (Some(node1), Some(node2)) = pair {
+                matching.insert(key(node1), node2.clone());
+            }
+        };
+        let mut push = |diff, node1: Option<&NodeRef>, node2: Option<&NodeRef>| {
+            differences.push((diff, node1.cloned(), node2.cloned()));
+            true
+        };
+        let mut walker = Walker::new(options, &mut push);
+        walker.steps = Some(&mut steps);
+        walk_sides(&side1, &side2, &mut walker);
+    }
+    differences.into_iter().map(|(diff, node1, node2)| {
+        let node = node2.or_else(|| {
+            node1?.ancestors().filter_map(|ancestor| matching.get(&key(&ancestor)).cloned()).next()
+        });
+        let offset = node.and_then(|node| {
+            node.inclusive_ancestors().filter_map(|n| tags.get(&n).map(|tag| tag.offset)).next()
+        });
+        let location = match offset {
+            // Not the `<svg>` put around the content.
+            Some(offset) if offset >= wrapper_len => line_column(content2, offset - wrapper_len),
+            _ => (1, 1),
+        };
+        (diff, location)
+    }).collect()
+}
+
+// Escapes the message of a workflow command.
+fn escape_data(s: &str) -> String {
+    s.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+// Escapes the value of a workflow command property.
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// Compares the two html content strings and returns one GitHub Actions `::error` workflow
+/// command per difference (one per line), so the differences are shown as annotations of
+/// `file_path`, the file of `content2`, at the same locations as with `render_with_location`.
+///
+/// ```
+/// use html_diff::{render_github_annotations, DiffOptions};
+///
+/// assert_eq!(render_github_annotations("<p>a</p>", "\n<p>b</p>", &DiffOptions::new(),
+///                                      "index.html"),
+///            "::error file=index.html,line=2,col=1,title=Texts differ::/html[0]/body[0]/p[0] => \
+///             [Texts differ]: expected \"a\", found \"b\"\n");
+/// ```
+pub fn render_github_annotations(content1: &str, content2: &str, options: &DiffOptions,
+                                 file_path: &str) -> String {
+    let file_path = escape_property(file_path);
+    located_differences(content1, content2, options).into_iter()
+                                                    .map(|(diff, (line, column))| {
+                                                        format!("::error file={},line={},col={},\
+                                                                 title={}::{}\n",
+                                                                file_path, line, column,
+                                                                escape_property(diff.label()),
+                                                                escape_data(&diff.to_string()))
+                                                    })
+                                                    .collect()
+}
+
+/// Compares the two html content strings and returns one `file:line:col: difference` line per
+/// difference, the format editors and terminals turn into links to `file_path`, the file of
+/// `content2`.
+///
+/// The location is the one of the start tag of the element of `content2` the difference was
+/// found on (the parent of a text node, the element in which a node of `content1` is missing).
+/// The elements created by the parser without a start tag (like `<tbody>`) are located at their
+/// closest ancestor which has one, and at `1:1` if there is none.
+///
+/// ```
+/// use html_diff::{render_with_location, DiffOptions};
+///
+/// assert_eq!(render_with_location("<ul><li>a</li></ul>", "<ul>\n  <li>b</li>\n</ul>",
+///                                 &DiffOptions::new(), "a.html"),
+///            "a.html:2:3: /html[0]/body[0]/ul[0]/li[0] => [Texts differ]: expected \"a\", \
+///             found \"b\"\n");
+/// ```
+pub fn render_with_location(content1: &str, content2: &str, options: &DiffOptions,
+                            file_path: &str) -> String {
+    located_differences(content1, content2, options).into_iter()
+                                                    .map(|(diff, (line, column))| {
+                                                        format!("{}:{}:{}: {}\n", file_path, line,
+                                                                column, diff)
+                                                    })
+                                                    .collect()
+}
@@ -0,0 +1,41 @@
+use Difference;
+
+// A pattern segment without position (`div`) matches the segment of any `div` (`div[3]`).
+fn segment_matches(pattern: &str, segment: &str) -> bool {
+    pattern == "*" || pattern == segment ||
+    (!pattern.contains('[') && segment.split('[').next() == Some(pattern))
+}
+
+fn segments_match(pattern: &[&str], segments: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => segments.is_empty(),
+        Some((&"**", rest)) => (0..=segments.len()).any(|i| segments_match(rest, &segments[i..])),
+        Some((first, rest)) => {
+            match segments.split_first() {
+                Some((segment, segments)) => {
+                    segment_matches(first, segment) && segments_match(rest, segments)
+                }
+                None => false,
+            }
+        }
+    }
+}
+
+/// Returns `true` if the path of the difference matches `pattern`.
+///
+/// The pattern is a list of segments separated by `/`: `*` matches exactly one segment, `**`
+/// matches any number of segments (including none) and a tag name without position (`div`)
+/// matches it at any position (`div[2]`).
+///
+/// ```
+/// use html_diff::{get_differences, path_glob_matches};
+///
+/// let differences = get_differences("<main><p>a</p></main>", "<main><p>b</p></main>");
+/// assert!(path_glob_matches(&differences[0], "/html/body/main/**"));
+/// assert!(path_glob_matches(&differences[0], "/*/body[0]/main/p"));
+/// assert!(!path_glob_matches(&differences[0], "/html/body/nav/**"));
+/// ```
+pub fn path_glob_matches(difference: &Difference, pattern: &str) -> bool {
+    let pattern = pattern.split('/').filter(|s| !s.is_empty()).collect::<Vec<_>>();
+    segments_match(&pattern, &difference.elem_information().path_segments())
+}
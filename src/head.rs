@@ -0,0 +1,120 @@
+use kuchiki::NodeRef;
+
+use {node_path, parse_document, Difference, ElementInformation};
+
+// The charset declared by `<meta charset>` or by `<meta http-equiv="Content-Type">`.
+fn declared_charset(content: &str) -> Option<String> {
+    let document = parse_document(content);
+    let metas = document.select("meta").ok()?;
+    for meta in metas {
+        let attributes = meta.attributes.borrow();
+        if let Some(charset) = attributes.get("charset") {
+            return Some(charset.trim().to_owned())
+        }
+        let is_content_type = attributes.get("http-equiv")
+                                        .map(|h| h.eq_ignore_ascii_case("content-type"))
+                                        .unwrap_or(false);
+        if !is_content_type {
+            continue
+        }
+        let content = attributes.get("content").unwrap_or("");
+        let charset = content.split(';').filter_map(|part| {
+            let mut parts = part.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) if key.trim().eq_ignore_ascii_case("charset") => {
+                    Some(value.trim().trim_matches(|c| c == '"' || c == '\'').to_owned())
+                }
+                _ => None,
+            }
+        }).next();
+        if charset.is_some() {
+            return charset
+        }
+    }
+    None
+}
+
+/// Returns the charsets declared by the two html content strings (with `<meta charset>` or
+/// `<meta http-equiv="Content-Type">`) if they differ, `None` if they are the same.
+///
+/// The charsets are compared case-insensitively, `None` means that no charset is declared.
+///
+/// ```
+/// use html_diff::charset_difference;
+///
+/// assert_eq!(charset_difference("<meta charset=\"utf-8\">", "<meta charset=\"UTF-8\">"), None);
+/// assert_eq!(charset_difference("<meta charset=\"utf-8\">", "<p>a</p>"),
+///            Some((Some("utf-8".to_owned()), None)));
+/// ```
+pub fn charset_difference(content1: &str,
+                          content2: &str) -> Option<(Option<String>, Option<String>)> {
+    let charset1 = declared_charset(content1);
+    let charset2 = declared_charset(content2);
+    let same = match (charset1.as_ref(), charset2.as_ref()) {
+        (Some(charset1), Some(charset2)) => charset1.eq_ignore_ascii_case(charset2),
+        (None, None) => true,
+        _ => false,
+    };
+    if same {
+        None
+    } else {
+        Some((charset1, charset2))
+    }
+}
+
+/// Returns the difference between the `<title>` of the two html content strings, `None` if
+/// they are the same.
+///
+/// The titles are compared with their whitespace collapsed (as browsers display them). A
+/// `Difference::NodeText` is returned if both titles exist and differ, a
+/// `Difference::NotPresent` if only one of the two documents has a title.
+///
+/// ```
+/// use html_diff::title_difference;
+///
+/// assert_eq!(title_difference("<title>Home</title>", "<title> Home\n</title>"), None);
+/// assert!(title_difference("<title>Home</title>", "<title>About</title>").unwrap()
+///                                                                       .is_node_text());
+/// assert!(title_difference("<title>Home</title>", "<p>a</p>").unwrap().is_not_present());
+/// ```
+pub fn title_difference(content1: &str, content2: &str) -> Option<Difference> {
+    // The documents must outlive the titles, the parents are only weak references.
+    let (document1, document2) = (parse_document(content1), parse_document(content2));
+    let title1 = document1.select_first("title").ok();
+    let title2 = document2.select_first("title").ok();
+    let text = |title: &NodeRef| title.text_contents().split_whitespace().collect::<Vec<_>>()
+                                                                       .join(" ");
+    let parent_path = |title: &NodeRef| {
+        let mut path = node_path(title);
+        path.pop();
+        path
+    };
+    match (title1, title2) {
+        (Some(title1), Some(title2)) => {
+            let (text1, text2) = (text(title1.as_node()), text(title2.as_node()));
+            if text1 == text2 {
+                return None
+            }
+            Some(Difference::NodeText {
+                elem: ElementInformation::from_path(&node_path(title1.as_node())),
+                elem_text: text1,
+                opposite_elem: ElementInformation::from_path(&node_path(title2.as_node())),
+                opposite_elem_text: text2,
+            })
+        }
+        (Some(title1), None) => {
+            Some(Difference::NotPresent {
+                elem: Some(ElementInformation::from_node(&title1, &parent_path(title1.as_node()))),
+                opposite_elem: None,
+            })
+        }
+        (None, Some(title2)) => {
+            Some(Difference::NotPresent {
+                elem: None,
+                opposite_elem: Some(ElementInformation::from_node(&title2,
+                                                                  &parent_path(title2.as_node()))),
+            })
+        }
+        (None, None) => None,
+    }
+}
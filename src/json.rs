@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// A parsed JSON value, the objects are sorted by key so their order doesn't matter when
+// comparing them.
+#[derive(Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(BTreeMap<String, Value>),
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(&c) = self.chars.peek() {
+            if c != ' ' && c != '\t' && c != '\n' && c != '\r' {
+                break
+            }
+            self.chars.next();
+        }
+    }
+
+    fn expect(&mut self, expected: &str) -> Option<()> {
+        for c in expected.chars() {
+            if self.chars.next()? != c {
+                return None
+            }
+        }
+        Some(())
+    }
+
+    fn parse_value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        let value = match *self.chars.peek()? {
+            'n' => self.expect("null").map(|_| Value::Null)?,
+            't' => self.expect("true").map(|_| Value::Bool(true))?,
+            'f' => self.expect("false").map(|_| Value::Bool(false))?,
+            '"' => Value::String(self.parse_string()?),
+            '[' => {
+                self.chars.next();
+                let mut values = Vec::new();
+                self.parse_sequence(']', |parser| {
+                    values.push(parser.parse_value()?);
+                    Some(())
+                })?;
+                Value::Array(values)
+            }
+            '{' => {
+                self.chars.next();
+                let mut values = BTreeMap::new();
+                self.parse_sequence('}', |parser| {
+                    parser.skip_whitespace();
+                    let key = parser.parse_string()?;
+                    parser.skip_whitespace();
+                    parser.expect(":")?;
+                    values.insert(key, parser.parse_value()?);
+                    Some(())
+                })?;
+                Value::Object(values)
+            }
+            _ => Value::Number(self.parse_number()?),
+        };
+        self.skip_whitespace();
+        Some(value)
+    }
+
+    // Parses the comma-separated items of an array or an object until `end`.
+    fn parse_sequence<F>(&mut self, end: char, mut parse_item: F) -> Option<()>
+        where F: FnMut(&mut Parser<'a>) -> Option<()>
+    {
+        self.skip_whitespace();
+        if self.chars.peek() == Some(&end) {
+            self.chars.next();
+            return Some(())
+        }
+        loop {
+            parse_item(self)?;
+            match self.chars.next()? {
+                ',' => {}
+                c if c == end => return Some(()),
+                _ => return None,
+            }
+        }
+    }
+
+    fn parse_hex(&mut self) -> Option<u32> {
+        let mut value = 0;
+        for _ in 0..4 {
+            value = value * 16 + self.chars.next()?.to_digit(16)?;
+        }
+        Some(value)
+    }
+
+    fn parse_string(&mut self) -> Option<String> {
+        self.expect("\"")?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(s),
+                '\\' => {
+                    let c = match self.chars.next()? {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let high = self.parse_hex()?;
+                            if (0xd800..0xdc00).contains(&high) {
+                                self.expect("\\u")?;
+                                let low = self.parse_hex()?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return None
+                                }
+                                ::std::char::from_u32(0x10000 + ((high - 0xd800) << 10) +
+                                                      (low - 0xdc00))?
+                            } else {
+                                ::std::char::from_u32(high)?
+                            }
+                        }
+                        _ => return None,
+                    };
+                    s.push(c);
+                }
+                c if c < ' ' => return None,
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Option<f64> {
+        let mut number = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !c.is_ascii_digit() && c != '-' && c != '+' && c != '.' && c != 'e' && c != 'E' {
+                break
+            }
+            number.push(c);
+            self.chars.next();
+        }
+        if !is_number(&number) {
+            return None
+        }
+        number.parse().ok()
+    }
+}
+
+// Whether `s` follows the number grammar of RFC 8259: `-? (0 | [1-9] digits) (. digits)?
+// ([eE] [+-]? digits)?`, so without a leading `+`, leading zeros or a bare `.`.
+fn is_number(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    let digits = |i: &mut usize| {
+        let start = *i;
+        while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+            *i += 1;
+        }
+        *i > start
+    };
+    if bytes.get(i) == Some(&b'-') {
+        i += 1;
+    }
+    match bytes.get(i) {
+        Some(&b'0') => i += 1,
+        Some(b) if b.is_ascii_digit() => {
+            digits(&mut i);
+        }
+        _ => return false,
+    }
+    if bytes.get(i) == Some(&b'.') {
+        i += 1;
+        if !digits(&mut i) {
+            return false
+        }
+    }
+    if bytes.get(i) == Some(&b'e') || bytes.get(i) == Some(&b'E') {
+        i += 1;
+        if bytes.get(i) == Some(&b'+') || bytes.get(i) == Some(&b'-') {
+            i += 1;
+        }
+        if !digits(&mut i) {
+            return false
+        }
+    }
+    i == bytes.len()
+}
+
+/// Parses `s` as a JSON value, returns `None` if it isn't valid JSON.
+pub fn parse(s: &str) -> Option<Value> {
+    let mut parser = Parser { chars: s.chars().peekable() };
+    let value = parser.parse_value()?;
+    match parser.chars.next() {
+        Some(_) => None,
+        None => Some(value),
+    }
+}
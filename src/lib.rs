@@ -1,23 +1,108 @@
 extern crate kuchiki;
 
 use kuchiki::traits::*;
-use kuchiki::{ElementData, NodeDataRef, NodeRef};
+use kuchiki::{Attributes, ElementData, NodeData, NodeDataRef};
 
+use source_tags::{SourceTag, SourceTags};
+
+use std::borrow::Cow;
+use std::cmp;
 use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::mem;
 
-#[derive(Debug, Clone, PartialEq)]
+pub use kuchiki::NodeRef;
+pub use apply::{apply_differences, ApplyError};
+pub use canonical::normalize;
+pub use annotated::{annotated_diff, annotated_diff_with_options, AnnotatedTree, Annotation};
+pub use bidirectional::{bidirectional_diff, bidirectional_diff_with_options, BiDiff};
+pub use error::DiffError;
+pub use github::{render_github_annotations, render_with_location};
+pub use glob::path_glob_matches;
+pub use head::{charset_difference, title_difference};
+pub use options::{DiffOptions, NESTED_INLINE_TAGS};
+pub use outline::heading_outline_differences;
+pub use tracking::is_tracking_element;
+pub use tree::{build_difference_tree, DiffTree};
+pub use validate::{validate, Validity};
+pub use warnings::{get_warnings, DiffWarning};
+
+mod annotated;
+mod apply;
+mod attributes;
+mod bidirectional;
+mod canonical;
+mod error;
+mod forms;
+mod github;
+mod glob;
+mod head;
+mod json;
+mod options;
+mod outline;
+mod source_tags;
+mod svg;
+mod text;
+mod tracking;
+mod tree;
+mod urls;
+mod validate;
+mod warnings;
+
+/// Information about one of the two nodes involved in a difference.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ElementInformation {
+    /// The tag name of the node (empty for text nodes).
     pub element_name: String,
+    /// The serialized node, including its whole subtree (`<p class="a">text <b>bold</b></p>`
+    /// for an element, with the attributes sorted by name, the escaped text for a text node).
+    ///
+    /// It is empty for `Difference::NodeText` since only the text is relevant there.
     pub element_content: String,
+    /// The path of the parent node, with the position of each element among its siblings of the
+    /// same name (`/html[0]/body[0]/div[1]`).
     pub path: String,
+    /// The number of segments in `path`.
+    pub depth: usize,
+    /// The opening tag of the parent element (`<div class="a">`, with the attributes sorted by
+    /// name). It is `None` at the document level and for the differences not found by walking
+    /// the two trees (like `text_only_differences`).
+    pub parent_content: Option<String>,
 }
 
 impl ElementInformation {
-    fn new<T: ToOutput>(content: &T, path: &[String]) -> ElementInformation {
+    /// Builds an `ElementInformation`, `depth` is computed from `path`.
+    ///
+    /// ```
+    /// use html_diff::ElementInformation;
+    ///
+    /// let info = ElementInformation::new("p", "<p>text</p>", "/html[0]/body[0]");
+    /// assert_eq!(info.depth, 2);
+    /// ```
+    pub fn new(element_name: &str, element_content: &str, path: &str) -> ElementInformation {
+        ElementInformation {
+            element_name: element_name.to_owned(),
+            element_content: element_content.to_owned(),
+            path: path.to_owned(),
+            depth: path.split('/').filter(|s| !s.is_empty()).count(),
+            parent_content: None,
+        }
+    }
+
+    /// Returns the segments of `path` (`["html[0]", "body[0]"]` for `/html[0]/body[0]`).
+    pub fn path_segments(&self) -> Vec<&str> {
+        self.path.split('/').filter(|s| !s.is_empty()).collect()
+    }
+
+    fn from_node<T: ToOutput>(content: &T, path: &[String]) -> ElementInformation {
         ElementInformation {
             element_name: content.name(),
             element_content: content.output(),
             path: path.join("/"),
+            depth: get_depth(path),
+            parent_content: None,
         }
     }
 
@@ -26,25 +111,97 @@ impl ElementInformation {
             element_name: String::new(),
             element_content: String::new(),
             path: path.join("/"),
+            depth: get_depth(path),
+            parent_content: None,
+        }
+    }
+}
+
+// The first element of `path` is always the empty root segment.
+fn get_depth(path: &[String]) -> usize {
+    path.len().saturating_sub(1)
+}
+
+// The html elements without closing tag and the ones whose texts aren't escaped, as html5ever
+// serializes them.
+const VOID_ELEMENTS: &[&str] = &["area", "base", "basefont", "bgsound", "br", "col", "embed",
+                                 "frame", "hr", "img", "input", "keygen", "link", "meta", "param",
+                                 "source", "track", "wbr"];
+const RAW_TEXT_ELEMENTS: &[&str] = &["style", "script", "xmp", "iframe", "noembed", "noframes",
+                                     "plaintext", "noscript"];
+
+fn write_escaped(s: &str, attribute: bool, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '\u{a0}' => out.push_str("&nbsp;"),
+            '"' if attribute => out.push_str("&quot;"),
+            '<' if !attribute => out.push_str("&lt;"),
+            '>' if !attribute => out.push_str("&gt;"),
+            c => out.push(c),
+        }
+    }
+}
+
+fn write_sorted(node: &NodeRef, raw_text: bool, out: &mut String) {
+    match *node.data() {
+        NodeData::Element(ref elem) => {
+            let is_html = *elem.name.ns == *"http://www.w3.org/1999/xhtml";
+            let mut attributes = elem.attributes.borrow().map.iter().map(|(k, v)| {
+                (attribute_name(k.prefix.as_deref(), &k.local), v.clone())
+            }).collect::<Vec<_>>();
+            attributes.sort();
+            out.push_str(&format!("<{}", elem.name.local));
+            for (name, value) in attributes {
+                out.push_str(&format!(" {}=\"", name));
+                write_escaped(&value, true, out);
+                out.push('"');
+            }
+            out.push('>');
+            if is_html && VOID_ELEMENTS.contains(&&*elem.name.local) {
+                return
+            }
+            let raw_text = is_html && RAW_TEXT_ELEMENTS.contains(&&*elem.name.local);
+            for child in node.children() {
+                write_sorted(&child, raw_text, out);
+            }
+            out.push_str(&format!("</{}>", elem.name.local));
+        }
+        NodeData::Text(ref text) if raw_text => out.push_str(&text.borrow()),
+        NodeData::Text(ref text) => write_escaped(&text.borrow(), false, out),
+        NodeData::Comment(ref text) => out.push_str(&format!("<!--{}-->", text.borrow())),
+        NodeData::ProcessingInstruction(ref contents) => {
+            let contents = contents.borrow();
+            out.push_str(&format!("<?{} {}>", contents.0, contents.1));
+        }
+        NodeData::Doctype(ref doctype) => out.push_str(&format!("<!DOCTYPE {}>", doctype.name)),
+        NodeData::Document(_) | NodeData::DocumentFragment => {
+            for child in node.children() {
+                write_sorted(&child, false, out);
+            }
         }
     }
 }
 
+// Serializes `node` like `NodeRef::to_string` but with the attributes sorted by name: the
+// parser keeps them in a `HashMap`, so the order of the serializer changes from one parse to
+// the other.
+fn serialize_sorted(node: &NodeRef) -> String {
+    let mut out = String::new();
+    write_sorted(node, false, &mut out);
+    out
+}
+
 trait ToOutput {
     fn output(&self) -> String;
     fn name(&self) -> String;
 }
 
+// The html5ever serializer always uses double quotes and escapes `"` (as `&quot;`) and `&` in
+// attribute values, so the output can be parsed again.
 impl ToOutput for NodeDataRef<ElementData> {
     fn output(&self) -> String {
-        format!("<{0} {1}>{2}</{0}>",
-                (*self).name.local,
-                (*(*self).attributes.borrow()).map
-                                              .iter()
-                                              .map(|(k, v)| format!("\"{}\"=\"{}\"", k.local, v))
-                                              .collect::<Vec<String>>()
-                                              .join(" "),
-                self.text_contents())
+        serialize_sorted(self.as_node())
     }
 
     fn name(&self) -> String {
@@ -54,24 +211,23 @@ impl ToOutput for NodeDataRef<ElementData> {
 
 impl ToOutput for NodeRef {
     fn output(&self) -> String {
-        self.to_string()
+        serialize_sorted(self)
     }
 
     fn name(&self) -> String {
-        if let Some(s) = self.to_string().split(' ').next().unwrap().split('>').next() {
-            if s.is_empty() {
-                String::new()
-            } else {
-                s.chars().skip(1).collect()
-            }
-        } else {
-            String::new()
+        match self.as_element() {
+            Some(elem) => format!("{}", elem.name.local),
+            None => String::new(),
         }
     }
 }
 
 /// Contains the kind of difference and some information.
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Two differences are equal (and have the same hash) if all their information is, including
+/// the paths of their elements, so the same change found at two places gives two different
+/// values in a `HashSet` (see `Difference::content_eq` to ignore the paths).
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Difference {
     /// Different node types at the same place (text vs data for example).
     NodeType {
@@ -83,7 +239,8 @@ pub enum Difference {
         elem: ElementInformation,
         opposite_elem: ElementInformation,
     },
-    /// Different attributes for two nodes.
+    /// Different attributes for two nodes. The names of the namespaced attributes include their
+    /// prefix (`xlink:href`).
     NodeAttributes {
         elem: ElementInformation,
         elem_attributes: HashMap<String, String>,
@@ -97,14 +254,129 @@ pub enum Difference {
         opposite_elem: ElementInformation,
         opposite_elem_text: String,
     },
-    /// If an element isn't present in one of the two sides.
+    /// If an element isn't present in one of the two sides. The descendants of the element
+    /// aren't gone through, so no other difference is reported for them.
     NotPresent {
         elem: Option<ElementInformation>,
         opposite_elem: Option<ElementInformation>,
     },
+    /// The subtrees of two nodes have `count` differences below the maximum depth (only emitted
+    /// with `DiffOptions::mark_subtrees_below_max_depth`).
+    SubtreeDiffers {
+        elem: ElementInformation,
+        opposite_elem: ElementInformation,
+        count: usize,
+    },
+    /// The same attributes written in a different order in the sources of two elements (only
+    /// emitted with `DiffOptions::strict_attribute_order`). The names are in source order.
+    AttributeOrder {
+        elem: ElementInformation,
+        elem_attributes: Vec<String>,
+        opposite_elem: ElementInformation,
+        opposite_elem_attributes: Vec<String>,
+    },
+}
+
+// The attributes are hashed sorted by name, so the equal differences have the same hash whatever
+// the order of their `HashMap`s.
+impl Hash for Difference {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        fn hash_attributes<H: Hasher>(attributes: &HashMap<String, String>, state: &mut H) {
+            let mut attributes = attributes.iter().collect::<Vec<_>>();
+            attributes.sort();
+            attributes.hash(state);
+        }
+
+        self.kind().hash(state);
+        match *self {
+            Difference::NodeType { ref elem, ref opposite_elem } |
+            Difference::NodeName { ref elem, ref opposite_elem } => {
+                elem.hash(state);
+                opposite_elem.hash(state);
+            }
+            Difference::NodeAttributes { ref elem,
+                                         ref elem_attributes,
+                                         ref opposite_elem,
+                                         ref opposite_elem_attributes } => {
+                elem.hash(state);
+                hash_attributes(elem_attributes, state);
+                opposite_elem.hash(state);
+                hash_attributes(opposite_elem_attributes, state);
+            }
+            Difference::NodeText { ref elem, ref elem_text, ref opposite_elem,
+                                   ref opposite_elem_text } => {
+                elem.hash(state);
+                elem_text.hash(state);
+                opposite_elem.hash(state);
+                opposite_elem_text.hash(state);
+            }
+            Difference::NotPresent { ref elem, ref opposite_elem } => {
+                elem.hash(state);
+                opposite_elem.hash(state);
+            }
+            Difference::SubtreeDiffers { ref elem, ref opposite_elem, count } => {
+                elem.hash(state);
+                opposite_elem.hash(state);
+                count.hash(state);
+            }
+            Difference::AttributeOrder { ref elem,
+                                         ref elem_attributes,
+                                         ref opposite_elem,
+                                         ref opposite_elem_attributes } => {
+                elem.hash(state);
+                elem_attributes.hash(state);
+                opposite_elem.hash(state);
+                opposite_elem_attributes.hash(state);
+            }
+        }
+    }
+}
+
+/// The kind of a `Difference`, without its content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DifferenceKind {
+    /// `Difference::NodeType`.
+    NodeType,
+    /// `Difference::NodeName`.
+    NodeName,
+    /// `Difference::NodeAttributes`.
+    NodeAttributes,
+    /// `Difference::NodeText`.
+    NodeText,
+    /// `Difference::NotPresent`.
+    NotPresent,
+    /// `Difference::SubtreeDiffers`.
+    SubtreeDiffers,
+    /// `Difference::AttributeOrder`.
+    AttributeOrder,
+}
+
+impl DifferenceKind {
+    /// All the kinds, in declaration order.
+    pub fn all() -> [DifferenceKind; 7] {
+        [DifferenceKind::NodeType,
+         DifferenceKind::NodeName,
+         DifferenceKind::NodeAttributes,
+         DifferenceKind::NodeText,
+         DifferenceKind::NotPresent,
+         DifferenceKind::SubtreeDiffers,
+         DifferenceKind::AttributeOrder]
+    }
 }
 
 impl Difference {
+    pub fn kind(&self) -> DifferenceKind {
+        match *self {
+            Difference::NodeType { .. } => DifferenceKind::NodeType,
+            Difference::NodeName { .. } => DifferenceKind::NodeName,
+            Difference::NodeAttributes { .. } => DifferenceKind::NodeAttributes,
+            Difference::NodeText { .. } => DifferenceKind::NodeText,
+            Difference::NotPresent { .. } => DifferenceKind::NotPresent,
+            Difference::SubtreeDiffers { .. } => DifferenceKind::SubtreeDiffers,
+            Difference::AttributeOrder { .. } => DifferenceKind::AttributeOrder,
+        }
+    }
+
     pub fn is_node_type(&self) -> bool {
         match *self {
             Difference::NodeType { .. } => true,
@@ -139,80 +411,488 @@ impl Difference {
             _ => false,
         }
     }
+
+    pub fn is_subtree_differs(&self) -> bool {
+        self.kind() == DifferenceKind::SubtreeDiffers
+    }
+
+    pub fn is_attribute_order(&self) -> bool {
+        self.kind() == DifferenceKind::AttributeOrder
+    }
+
+    /// Returns `true` if the difference changes the structure of the document: a node of
+    /// another type or name, a missing element or a subtree differing below the maximum depth.
+    /// The texts, the attributes and the missing text nodes aren't structural.
+    pub fn is_structural(&self) -> bool {
+        match *self {
+            Difference::NodeType { .. } |
+            Difference::NodeName { .. } |
+            Difference::SubtreeDiffers { .. } => true,
+            Difference::NotPresent { .. } => !self.element_name().is_empty(),
+            Difference::NodeAttributes { .. } |
+            Difference::NodeText { .. } |
+            Difference::AttributeOrder { .. } => false,
+        }
+    }
+
+    /// Returns `true` if this is a `NodeAttributes` difference in which every differing
+    /// attribute is present on both sides and only differs by whitespace (`class=" a  b "` vs
+    /// `class="a b"` for example).
+    pub fn is_whitespace_only_attribute(&self) -> bool {
+        match *self {
+            Difference::NodeAttributes { ref elem_attributes,
+                                         ref opposite_elem_attributes, .. } => {
+                elem_attributes.len() == opposite_elem_attributes.len() &&
+                elem_attributes.iter().all(|(k, v)| {
+                    match opposite_elem_attributes.get(k) {
+                        Some(v2) => v == v2 || attributes::collapse_whitespace(v) ==
+                                               attributes::collapse_whitespace(v2),
+                        None => false,
+                    }
+                })
+            }
+            _ => false,
+        }
+    }
+}
+
+/// How much information `Difference::render` outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verbosity {
+    /// One short line with the path and the kind of difference.
+    Terse,
+    /// The same output as the `Display` implementation.
+    Normal,
+    /// Several lines with the complete path, the full content of both elements and all their
+    /// attributes.
+    Verbose,
+}
+
+fn format_attributes(attributes: &HashMap<String, String>) -> String {
+    let mut attributes = attributes.iter()
+                                   .map(|(k, v)| format!("{}={:?}", k, v))
+                                   .collect::<Vec<_>>();
+    attributes.sort();
+    attributes.join(" ")
+}
+
+// The information of the invalid `Difference::NotPresent` without any element.
+static NO_INFORMATION: ElementInformation = ElementInformation {
+    element_name: String::new(),
+    element_content: String::new(),
+    path: String::new(),
+    depth: 0,
+    parent_content: None,
+};
+
+impl Difference {
+    // The information of the side that is always present (`elem` unless `NotPresent` says
+    // otherwise).
+    fn elem_information(&self) -> &ElementInformation {
+        match *self {
+            Difference::NodeType { ref elem, .. } |
+            Difference::NodeName { ref elem, .. } |
+            Difference::NodeAttributes { ref elem, .. } |
+            Difference::NodeText { ref elem, .. } |
+            Difference::SubtreeDiffers { ref elem, .. } |
+            Difference::AttributeOrder { ref elem, .. } => elem,
+            Difference::NotPresent { ref elem, ref opposite_elem } => {
+                elem.as_ref().or(opposite_elem.as_ref()).unwrap_or(&NO_INFORMATION)
+            }
+        }
+    }
+
+    /// Returns the tag name of the element involved in the difference (of `elem`, or of
+    /// `opposite_elem` if `elem` is missing). It is empty for text nodes, so for
+    /// `Difference::NodeText`.
+    pub fn element_name(&self) -> &str {
+        &self.elem_information().element_name
+    }
+
+    /// Returns the path of the parent of the element involved in the difference (see
+    /// `ElementInformation::path`).
+    pub fn path(&self) -> &str {
+        &self.elem_information().path
+    }
+
+    fn elements_mut(&mut self) -> Vec<&mut ElementInformation> {
+        match *self {
+            Difference::NodeType { ref mut elem, ref mut opposite_elem } |
+            Difference::NodeName { ref mut elem, ref mut opposite_elem } |
+            Difference::NodeAttributes { ref mut elem, ref mut opposite_elem, .. } |
+            Difference::NodeText { ref mut elem, ref mut opposite_elem, .. } |
+            Difference::SubtreeDiffers { ref mut elem, ref mut opposite_elem, .. } |
+            Difference::AttributeOrder { ref mut elem, ref mut opposite_elem, .. } => {
+                vec![elem, opposite_elem]
+            }
+            Difference::NotPresent { ref mut elem, ref mut opposite_elem } => {
+                elem.iter_mut().chain(opposite_elem.iter_mut()).collect()
+            }
+        }
+    }
+
+    fn set_parent_contents(&mut self, parent1: Option<String>, parent2: Option<String>) {
+        match *self {
+            Difference::NodeType { ref mut elem, ref mut opposite_elem } |
+            Difference::NodeName { ref mut elem, ref mut opposite_elem } |
+            Difference::NodeAttributes { ref mut elem, ref mut opposite_elem, .. } |
+            Difference::NodeText { ref mut elem, ref mut opposite_elem, .. } |
+            Difference::SubtreeDiffers { ref mut elem, ref mut opposite_elem, .. } |
+            Difference::AttributeOrder { ref mut elem, ref mut opposite_elem, .. } => {
+                elem.parent_content = parent1;
+                opposite_elem.parent_content = parent2;
+            }
+            Difference::NotPresent { ref mut elem, ref mut opposite_elem } => {
+                if let Some(ref mut elem) = *elem {
+                    elem.parent_content = parent1;
+                }
+                if let Some(ref mut elem) = *opposite_elem {
+                    elem.parent_content = parent2;
+                }
+            }
+        }
+    }
+
+    /// Same as `==` but the paths (with the depths and the parents) of the elements aren't
+    /// compared, so the same change is recognized even if it moved somewhere else in the
+    /// document.
+    pub fn content_eq(&self, other: &Difference) -> bool {
+        let (mut diff1, mut diff2) = (self.clone(), other.clone());
+        for elem in diff1.elements_mut().into_iter().chain(diff2.elements_mut()) {
+            elem.path.clear();
+            elem.depth = 0;
+            elem.parent_content = None;
+        }
+        diff1 == diff2
+    }
+
+    /// Returns an identifier of the difference computed from its kind, its path and its
+    /// content (the elements, attributes and texts), so the same difference found in another
+    /// run (or by another version of the crate) gets the same identifier. The parents
+    /// (`ElementInformation::parent_content`) aren't taken into account.
+    ///
+    /// ```
+    /// use html_diff::get_differences;
+    ///
+    /// let id = |content: &str| get_differences(content, "<p>x</p>")[0].stable_id();
+    /// assert_eq!(id("<p class=\"a\" id=\"b\">x</p>"), id("<p id=\"b\" class=\"a\">x</p>"));
+    /// assert_ne!(id("<p class=\"a\" id=\"b\">x</p>"), id("<p id=\"c\">x</p>"));
+    /// ```
+    pub fn stable_id(&self) -> u64 {
+        // FNV-1a, which doesn't depend on the standard library version like `DefaultHasher`.
+        let mut hash = 0xcbf2_9ce4_8422_2325u64;
+        let mut feed = |s: &str| {
+            for byte in s.bytes().chain(Some(0)) {
+                hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        feed(&format!("{:?}", self.kind()));
+        let sorted = |attributes: &HashMap<String, String>| {
+            let mut attributes = attributes.iter().collect::<Vec<_>>();
+            attributes.sort();
+            format!("{:?}", attributes)
+        };
+        match *self {
+            Difference::NodeAttributes { ref elem_attributes,
+                                         ref opposite_elem_attributes, .. } => {
+                feed(&sorted(elem_attributes));
+                feed(&sorted(opposite_elem_attributes));
+            }
+            Difference::NodeText { ref elem_text, ref opposite_elem_text, .. } => {
+                feed(elem_text);
+                feed(opposite_elem_text);
+            }
+            Difference::NotPresent { ref elem, .. } => {
+                feed(if elem.is_some() { "elem" } else { "opposite_elem" })
+            }
+            Difference::SubtreeDiffers { count, .. } => feed(&count.to_string()),
+            Difference::AttributeOrder { ref elem_attributes,
+                                         ref opposite_elem_attributes, .. } => {
+                feed(&elem_attributes.join(" "));
+                feed(&opposite_elem_attributes.join(" "));
+            }
+            Difference::NodeType { .. } | Difference::NodeName { .. } => {}
+        }
+        for elem in self.clone().elements_mut() {
+            feed(&elem.element_name);
+            feed(&elem.path);
+            feed(&elem.element_content);
+        }
+        hash
+    }
+
+    /// Returns the same difference as if the two contents had been compared the other way
+    /// around: `elem` and `opposite_elem` (and their attributes or texts) are swapped.
+    pub fn reverse(self) -> Difference {
+        match self {
+            Difference::NodeType { elem, opposite_elem } => {
+                Difference::NodeType { elem: opposite_elem, opposite_elem: elem }
+            }
+            Difference::NodeName { elem, opposite_elem } => {
+                Difference::NodeName { elem: opposite_elem, opposite_elem: elem }
+            }
+            Difference::NodeAttributes { elem,
+                                         elem_attributes,
+                                         opposite_elem,
+                                         opposite_elem_attributes } => {
+                Difference::NodeAttributes {
+                    elem: opposite_elem,
+                    elem_attributes: opposite_elem_attributes,
+                    opposite_elem: elem,
+                    opposite_elem_attributes: elem_attributes,
+                }
+            }
+            Difference::NodeText { elem, elem_text, opposite_elem, opposite_elem_text } => {
+                Difference::NodeText {
+                    elem: opposite_elem,
+                    elem_text: opposite_elem_text,
+                    opposite_elem: elem,
+                    opposite_elem_text: elem_text,
+                }
+            }
+            Difference::NotPresent { elem, opposite_elem } => {
+                Difference::NotPresent { elem: opposite_elem, opposite_elem: elem }
+            }
+            Difference::SubtreeDiffers { elem, opposite_elem, count } => {
+                Difference::SubtreeDiffers { elem: opposite_elem, opposite_elem: elem, count }
+            }
+            Difference::AttributeOrder { elem,
+                                         elem_attributes,
+                                         opposite_elem,
+                                         opposite_elem_attributes } => {
+                Difference::AttributeOrder {
+                    elem: opposite_elem,
+                    elem_attributes: opposite_elem_attributes,
+                    opposite_elem: elem,
+                    opposite_elem_attributes: elem_attributes,
+                }
+            }
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match *self {
+            Difference::NodeType { .. } => "Types differ",
+            Difference::NodeName { .. } => "Tags differ",
+            Difference::NodeAttributes { .. } => "Attributes differ",
+            Difference::NodeText { .. } => "Texts differ",
+            Difference::SubtreeDiffers { .. } => "Subtree differs below max depth",
+            Difference::AttributeOrder { .. } => "Attribute order differs",
+            Difference::NotPresent { ref elem, ref opposite_elem } => {
+                match (elem, opposite_elem) {
+                    (&Some(_), _) => "One element is missing",
+                    (&None, &Some(_)) => "Unexpected element",
+                    (&None, &None) => "Invalid difference",
+                }
+            }
+        }
+    }
+
+    /// Formats the difference with the given level of details.
+    pub fn render(&self, verbosity: Verbosity) -> String {
+        match verbosity {
+            Verbosity::Terse => format!("{} => [{}]", self.elem_information().path, self.label()),
+            Verbosity::Normal => self.to_string(),
+            Verbosity::Verbose => {
+                let elem = self.elem_information();
+                let mut lines = vec![format!("[{}]", self.label()),
+                                     format!("    path: {}", elem.path)];
+                match *self {
+                    Difference::NodeType { ref elem, ref opposite_elem } |
+                    Difference::NodeName { ref elem, ref opposite_elem } => {
+                        lines.push(format!("    expected: {}", elem.element_content));
+                        lines.push(format!("    found: {}", opposite_elem.element_content));
+                    }
+                    Difference::NodeAttributes { ref elem,
+                                                 ref elem_attributes,
+                                                 ref opposite_elem,
+                                                 ref opposite_elem_attributes } => {
+                        lines.push(format!("    expected: {}", elem.element_content));
+                        lines.push(format!("    found: {}", opposite_elem.element_content));
+                        lines.push(format!("    expected attributes: {}",
+                                           format_attributes(elem_attributes)));
+                        lines.push(format!("    found attributes: {}",
+                                           format_attributes(opposite_elem_attributes)));
+                    }
+                    Difference::NodeText { ref elem_text, ref opposite_elem_text, .. } => {
+                        lines.push(format!("    expected: {:?}", elem_text));
+                        lines.push(format!("    found: {:?}", opposite_elem_text));
+                    }
+                    Difference::SubtreeDiffers { ref elem, ref opposite_elem, count } => {
+                        lines.push(format!("    differences: {}", count));
+                        lines.push(format!("    expected: {}", elem.element_content));
+                        lines.push(format!("    found: {}", opposite_elem.element_content));
+                    }
+                    Difference::AttributeOrder { ref elem,
+                                                 ref elem_attributes,
+                                                 ref opposite_elem,
+                                                 ref opposite_elem_attributes } => {
+                        lines.push(format!("    expected: {}", elem.element_content));
+                        lines.push(format!("    found: {}", opposite_elem.element_content));
+                        lines.push(format!("    expected order: {}", elem_attributes.join(" ")));
+                        lines.push(format!("    found order: {}",
+                                           opposite_elem_attributes.join(" ")));
+                    }
+                    Difference::NotPresent { ref elem, ref opposite_elem } => {
+                        if let Some(ref elem) = *elem {
+                            lines.push(format!("    expected: {}", elem.element_content));
+                        }
+                        if let Some(ref elem) = *opposite_elem {
+                            lines.push(format!("    found: {}", elem.element_content));
+                        }
+                    }
+                }
+                lines.join("\n")
+            }
+        }
+    }
 }
 
-impl ToString for Difference {
-    fn to_string(&self) -> String {
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Difference::NodeType { ref elem, ref opposite_elem } => {
-                format!("{} => [Types differ]: expected \"{}\", found \"{}\"",
-                        elem.path, elem.element_name, opposite_elem.element_name)
+                write!(f, "{} => [Types differ]: expected \"{}\", found \"{}\"",
+                          elem.path, elem.element_name, opposite_elem.element_name)
             }
             Difference::NodeName { ref elem, ref opposite_elem } => {
-                format!("{} => [Tags differ]: expected \"{}\", found \"{}\"",
-                        elem.path, elem.element_name, opposite_elem.element_name)
+                write!(f, "{} => [Tags differ]: expected \"{}\", found \"{}\"",
+                          elem.path, elem.element_name, opposite_elem.element_name)
             }
             Difference::NodeAttributes { ref elem,
                                          ref elem_attributes,
                                          ref opposite_elem_attributes,
                                          .. } => {
-                format!("{} => [Attributes differ in \"{}\"]: expected \"{:?}\", found \"{:?}\"",
-                        elem.path, elem.element_name, elem_attributes, opposite_elem_attributes)
+                write!(f, "{} => [Attributes differ in \"{}\"]: expected \"{:?}\", found \"{:?}\"",
+                          elem.path, elem.element_name, elem_attributes, opposite_elem_attributes)
             }
             Difference::NodeText { ref elem, ref elem_text, ref opposite_elem_text, .. } => {
-                format!("{} => [Texts differ]: expected {:?}, found {:?}",
-                        elem.path, elem_text, opposite_elem_text)
+                write!(f, "{} => [Texts differ]: expected {:?}, found {:?}",
+                          elem.path, elem_text, opposite_elem_text)
             }
             Difference::NotPresent { ref elem, ref opposite_elem } => {
-                if let Some(ref elem) = *elem {
-                    format!("{} => [One element is missing]: expected {:?}",
-                            elem.path, elem.element_name)
-                } else if let Some(ref elem) = *opposite_elem {
-                    format!("{} => [Unexpected element \"{}\"]: found {:?}",
-                            elem.path, elem.element_name, elem.element_content)
-                } else {
-                    unreachable!()
+                match (elem, opposite_elem) {
+                    (Some(elem), _) => {
+                        write!(f, "{} => [One element is missing]: {} present in left but \
+                                   missing in right", elem.path, describe_node(elem))
+                    }
+                    (None, Some(elem)) => {
+                        write!(f, "{} => [Unexpected element]: {} present in right but missing \
+                                   in left", elem.path, describe_node(elem))
+                    }
+                    (None, None) => write!(f, " => [Invalid difference]: no element on any side"),
                 }
             }
+            Difference::SubtreeDiffers { ref elem, count, .. } => {
+                write!(f, "{} => [Subtree differs below max depth in \"{}\"]: {} difference(s)",
+                          elem.path, elem.element_name, count)
+            }
+            Difference::AttributeOrder { ref elem,
+                                         ref elem_attributes,
+                                         ref opposite_elem_attributes,
+                                         .. } => {
+                write!(f, "{} => [Attribute order differs in \"{}\"]: expected {:?}, found {:?}",
+                          elem.path, elem.element_name, elem_attributes, opposite_elem_attributes)
+            }
+        }
+    }
+}
+
+// `element <ul>` for an element, `text "content"` for a text.
+fn describe_node(elem: &ElementInformation) -> String {
+    if elem.element_name.is_empty() {
+        format!("text {:?}", elem.element_content)
+    } else {
+        format!("element <{}>", elem.element_name)
+    }
+}
+
+// The name of an attribute with its namespace prefix if it has one (`xlink:href`).
+fn attribute_name(prefix: Option<&str>, local: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}:{}", prefix, local),
+        None => local.to_owned(),
+    }
+}
+
+fn map_conversion(attributes: &Attributes, lowercase: bool,
+                  options: &DiffOptions) -> HashMap<String, String> {
+    let mut result = HashMap::with_capacity(attributes.map.len());
+    for (k, v) in &attributes.map {
+        let name = attribute_name(k.prefix.as_deref(), &k.local);
+        let name = if lowercase {
+            name.to_lowercase()
+        } else {
+            name
+        };
+        if attributes::is_ignored(&name, options) {
+            continue
+        }
+        result.insert(name, v.clone());
+    }
+    result
+}
+
+fn element_attributes(elem: &NodeDataRef<ElementData>,
+                      options: &DiffOptions) -> HashMap<String, String> {
+    let lowercase = options.normalize_dom && *elem.name.ns == *"http://www.w3.org/1999/xhtml";
+    let mut attributes = map_conversion(&elem.attributes.borrow(), lowercase, options);
+    if options.form_semantics {
+        forms::apply_semantics(elem.as_node(), &elem.name.local, &mut attributes);
+        // The attributes it adds might be ones which aren't compared.
+        attributes.retain(|name, _| !attributes::is_ignored(name, options));
+    }
+    if options.compare_effective_lang {
+        attributes.remove("lang");
+        if let Some(lang) = effective_lang(elem.as_node()) {
+            attributes.insert("lang".to_owned(), lang);
         }
     }
+    attributes
+}
+
+// The (lowercased) `lang` inherited by `node` if it has text of its own, `None` otherwise.
+fn effective_lang(node: &NodeRef) -> Option<String> {
+    let has_text = node.children().any(|c| {
+        c.as_text().map(|t| !t.borrow().trim().is_empty()).unwrap_or(false)
+    });
+    if !has_text {
+        return None
+    }
+    node.inclusive_ancestors()
+        .filter_map(|n| n.as_element().and_then(|e| e.attributes.borrow().get("lang")
+                                                                .map(|l| l.to_lowercase())))
+        .next()
 }
 
 fn check_elements(elem1: &NodeDataRef<ElementData>,
                   elem2: &NodeDataRef<ElementData>,
-                  path: &[String]) -> Option<Difference> {
+                  path: &[String],
+                  options: &DiffOptions) -> Option<Difference> {
     let e1: &ElementData = &*elem1;
     let e2: &ElementData = &*elem2;
-    if e1.name != e2.name {
+    if e1.name != e2.name && !options.are_equivalent_tags(&e1.name.local, &e2.name.local) {
         Some(Difference::NodeName {
-            elem: ElementInformation::new(elem1, path),
-            opposite_elem: ElementInformation::new(elem2, path),
+            elem: ElementInformation::from_node(elem1, path),
+            opposite_elem: ElementInformation::from_node(elem2, path),
         })
     } else {
-        let all_attributes_match =
-            (*e1.attributes.borrow()).map.iter().any(|(k, v)| {
-                (*e2.attributes.borrow()).map.get(k) != Some(v)
-            });
-        if all_attributes_match {
+        let attributes1 = element_attributes(elem1, options);
+        let attributes2 = element_attributes(elem2, options);
+        let attributes_differ = (!options.allow_attribute_additions &&
+                                 attributes1.len() != attributes2.len()) ||
+                                attributes1.iter().any(|(k, v)| {
+            match attributes2.get(k) {
+                Some(v2) => !attributes::values_match(k, v, v2, options),
+                None => true,
+            }
+        });
+        if attributes_differ {
             Some(Difference::NodeAttributes {
-                elem: ElementInformation::new(elem1, path),
-                elem_attributes: {
-                    let map = &(*e1.attributes.borrow()).map;
-                    let mut result = HashMap::with_capacity(map.len());
-                    for (k, v) in map {
-                        result.insert(format!("{}", k.local), v.clone());
-                    }
-                    result
-                },
-                opposite_elem: ElementInformation::new(elem2, path),
-                opposite_elem_attributes: {
-                    let map = &(*e2.attributes.borrow()).map;
-                    let mut result = HashMap::with_capacity(map.len());
-                    for (k, v) in map {
-                        result.insert(format!("{}", k.local), v.clone());
-                    }
-                    result
-                },
+                elem: ElementInformation::from_node(elem1, path),
+                elem_attributes: attributes1,
+                opposite_elem: ElementInformation::from_node(elem2, path),
+                opposite_elem_attributes: attributes2,
             })
         } else {
             None
@@ -229,293 +909,4082 @@ fn check_if_comment_or_empty_text(e: &NodeRef) -> bool {
     }
 }
 
-fn go_through_tree(element1: &NodeRef, element2: &NodeRef,
-                   path: &mut Vec<String>) -> Vec<Difference> {
-    let mut differences = Vec::new();
-    let mut pos: HashMap<String, usize> = HashMap::new();
-    let mut it1 = element1.children().filter(|e| check_if_comment_or_empty_text(e));
-    let mut it2 = element2.children().filter(|e| check_if_comment_or_empty_text(e));
+// Outside of foreign content (SVG, MathML), the parser turns CDATA sections into comments
+// containing `[CDATA[...]]`.
+fn get_cdata_content(e: &NodeRef) -> Option<String> {
+    e.as_comment().and_then(|c| {
+        let c = c.borrow();
+        if c.starts_with("[CDATA[") && c.ends_with("]]") {
+            Some(c["[CDATA[".len()..c.len() - 2].to_owned())
+        } else {
+            None
+        }
+    })
+}
+
+// The HTML parser turns processing instructions into comments but they can still be found in
+// trees built by hand (see `get_differences_against`).
+fn is_processing_instruction(e: &NodeRef) -> bool {
+    if let NodeData::ProcessingInstruction(_) = *e.data() {
+        return true
+    }
+    false
+}
+
+// The text of `element` if it's one of the `DiffOptions::transparent_inline_tags` and only
+// contains text, or a line break for a `<br>` with `DiffOptions::br_as_newline`.
+fn transparent_text(element: &NodeRef, options: &DiffOptions) -> Option<String> {
+    let elem = element.as_element()?;
+    if options.br_as_newline && &*elem.name.local == "br" {
+        return Some("\n".to_owned())
+    }
+    if !options.transparent_inline_tags.iter().any(|t| **t == *elem.name.local) {
+        return None
+    }
+    let children = get_children(element, options);
+    if children.is_empty() {
+        return None
+    }
+    children.iter().map(|c| c.as_text().map(|t| t.borrow().clone())).collect()
+}
+
+// Returns the innermost of the elements directly nested in `element` with the same name and
+// attributes, among the tags of `DiffOptions::collapse_nested_inline`, `element` itself if
+// there are none.
+fn collapse_nested_inline(element: NodeRef, options: &DiffOptions) -> NodeRef {
+    let tags = &options.collapse_nested_inline;
+    let mut element = element;
     loop {
-        let (element1, element2) = (it1.next(), it2.next());
-        if let Some(diff) = match (&element1, &element2) {
-            (&Some(ref element1), &Some(ref element2)) => {
-                match (element1.clone().into_element_ref(), element2.clone().into_element_ref()) {
-                    (Some(e1), Some(e2)) => check_elements(&e1, &e2, path),
-                    (None, None) => {
-                        match (element1.as_text(), element2.as_text()) {
-                            (Some(t1), Some(t2)) => {
-                                if t1 != t2 {
-                                    Some(Difference::NodeText {
-                                        elem: ElementInformation::from_path(path),
-                                        elem_text: t1.borrow().clone(),
-                                        opposite_elem: ElementInformation::from_path(path),
-                                        opposite_elem_text: t2.borrow().clone(),
-                                    })
-                                } else {
-                                    None
-                                }
-                            }
-                            (None, None) => None,
-                            _ => {
-                                Some(Difference::NodeType {
-                                    elem: ElementInformation::new(element1, path),
-                                    opposite_elem: ElementInformation::new(element2, path),
-                                })
-                            }
-                        }
-                    }
-                    _ => {
-                        Some(Difference::NodeType {
-                            elem: ElementInformation::new(element1, path),
-                            opposite_elem: ElementInformation::new(element2, path),
-                        })
+        let inner = {
+            let elem = match element.as_element() {
+                Some(elem) if tags.iter().any(|tag| *tag == *elem.name.local) => elem,
+                _ => break,
+            };
+            let mut children = element.children().filter(check_if_comment_or_empty_text);
+            match (children.next(), children.next()) {
+                (Some(child), None) => {
+                    let is_identical = child.as_element().map(|c| {
+                        c.name == elem.name && *c.attributes.borrow() == *elem.attributes.borrow()
+                    }).unwrap_or(false);
+                    if !is_identical {
+                        break
                     }
+                    child
                 }
+                _ => break,
             }
-            (&Some(ref elem1), &None) => {
-                Some(Difference::NotPresent {
-                    elem: Some(ElementInformation::new(elem1, path)),
-                    opposite_elem: None,
-                })
-            }
-            (&None, &Some(ref elem2)) => {
-                Some(Difference::NotPresent {
-                    elem: None,
-                    opposite_elem: Some(ElementInformation::new(elem2, path)),
-                })
+        };
+        element = inner;
+    }
+    element
+}
+
+// Returns `true` if `element` has the `hidden` attribute or `display: none` in its style.
+fn is_hidden(element: &NodeRef) -> bool {
+    let elem = match element.as_element() {
+        Some(elem) => elem,
+        None => return false,
+    };
+    let attributes = elem.attributes.borrow();
+    attributes.contains("hidden") ||
+    attributes.get("style")
+              .and_then(|style| attributes::style_property(style, "display"))
+              .map(|display| display == "none")
+              .unwrap_or(false)
+}
+
+/// Returns the children of `element` which have to be compared.
+///
+/// Comments, processing instructions and, if enabled, hidden and ignored elements are dropped
+/// and, if enabled, adjacent text nodes are merged into one (detached) text node so that two
+/// documents splitting the same text differently still match.
+///
+/// CDATA sections are compared as text, like the parser already does in foreign content.
+///
+/// The children of a `<template>` are the ones of its content.
+///
+/// With `DiffOptions::normalize_tables`, the rows of the `<thead>`, `<tbody>` and `<tfoot>`
+/// sections are returned as the children of their `<table>`.
+fn get_children(element: &NodeRef, options: &DiffOptions) -> Vec<NodeRef> {
+    let mut children: Vec<NodeRef> = Vec::new();
+    let template_contents = element.as_element().and_then(|e| e.template_contents.clone());
+    let it = template_contents.as_ref().unwrap_or(element).children().filter_map(|e| {
+        match get_cdata_content(&e) {
+            Some(content) => Some(NodeRef::new_text(content)),
+            None if e.as_comment().is_some() || is_processing_instruction(&e) => None,
+            None if options.ignore_hidden && is_hidden(&e) => None,
+            None if !options.is_in_selected_state(&e) => None,
+            None if options.ignore_elements.as_ref()
+                                           .map(|f| e.as_element().is_some() && f.call(&e))
+                                           .unwrap_or(false) => None,
+            None => {
+                let e = collapse_nested_inline(e, options);
+                Some(transparent_text(&e, options).map(NodeRef::new_text).unwrap_or(e))
             }
-            (&None, &None) => break,
-        } {
-            // need to add parent content
-            differences.push(diff);
-            continue
         }
-        let need_pop = if let Some(ref elem) = element1 {
-            if let Some(elem) = elem.as_element() {
-                let pos = pos.entry((*elem.name.local).to_owned()).or_insert(0);
-                path.push(format!("{}[{}]", elem.name.local, *pos));
-                *pos += 1;
-                true
-            } else {
-                false
+    });
+    for child in it {
+        if options.collapse_text_nodes {
+            if let Some(text) = child.as_text() {
+                let merged = match children.last() {
+                    Some(last) => last.as_text().map(|prev| format!("{}{}", prev.borrow(),
+                                                                     text.borrow())),
+                    None => None,
+                };
+                if let Some(merged) = merged {
+                    children.pop();
+                    children.push(NodeRef::new_text(merged));
+                    continue
+                }
             }
-        } else {
-            false
-        };
-        differences.extend_from_slice(&go_through_tree(&element1.unwrap(),
-                                                       &element2.unwrap(),
-                                                       path));
-        if need_pop {
-            path.pop();
         }
+        children.push(child);
     }
-    differences
+    children.retain(check_if_comment_or_empty_text);
+    if options.elements_only {
+        children.retain(|child| child.as_element().is_some());
+    }
+    if options.normalize_tables && is_element_named(element, &["table"]) {
+        children.sort_by_key(table_section_order);
+        children = children.into_iter()
+                           .flat_map(|child| {
+                               let is_flattened =
+                                   is_element_named(&child, &["thead", "tbody", "tfoot"]) ||
+                                   (is_element_named(&child, &["colgroup"]) &&
+                                    child.children().any(|c| is_element_named(&c, &["col"])));
+                               if is_flattened {
+                                   get_children(&child, options)
+                               } else {
+                                   vec![child]
+                               }
+                           })
+                           .collect();
+    }
+    children
 }
 
-/// Take two html content strings in output, returns a `Vec` containing the differences (if any).
-pub fn get_differences(content1: &str, content2: &str) -> Vec<Difference> {
-    go_through_tree(&kuchiki::parse_html().one(content1), &kuchiki::parse_html().one(content2),
-                    &mut vec![String::new()])
+// The position of the children of a `<table>` in its canonical order (the body is everything
+// which isn't a caption, a column or a header or footer section).
+fn table_section_order(child: &NodeRef) -> u8 {
+    if is_element_named(child, &["caption"]) {
+        0
+    } else if is_element_named(child, &["colgroup", "col"]) {
+        1
+    } else if is_element_named(child, &["thead"]) {
+        2
+    } else if is_element_named(child, &["tfoot"]) {
+        4
+    } else {
+        3
+    }
 }
 
-#[test]
-fn basic_diff() {
-    let original = "<div><foo></foo></div>";
-    let other = "<div><p></p></div>";
+fn is_element_named(node: &NodeRef, names: &[&str]) -> bool {
+    match node.as_element() {
+        Some(elem) => names.iter().any(|n| **n == *elem.name.local),
+        None => false,
+    }
+}
 
-    let differences = get_differences(original, other);
-    assert_eq!(differences.len(), 1, "{:?}", differences);
-    assert_eq!(differences[0].is_node_name(), true, "{:?}", differences[0]);
+// Returns the only child of `wrapper` if `wrapper` is one of the
+// `DiffOptions::unwrap_single_child_tags` and its child has the same name as `other` (which
+// doesn't have the same name as `wrapper`).
+fn unwrap_single_child(wrapper: &NodeRef, other: &NodeRef,
+                       options: &DiffOptions) -> Option<NodeRef> {
+    let (elem, other) = (wrapper.as_element()?, other.as_element()?);
+    if elem.name == other.name ||
+       !options.unwrap_single_child_tags.iter().any(|t| **t == *elem.name.local) {
+        return None
+    }
+    let mut children = get_children(wrapper, options);
+    if children.len() != 1 {
+        return None
+    }
+    let child = children.pop()?;
+    let is_same_name = child.as_element().map(|c| c.name == other.name).unwrap_or(false);
+    if is_same_name {
+        Some(child)
+    } else {
+        None
+    }
 }
 
-// Test if we stop correctly at first difference and don't go down.
-#[test]
-fn children_diff() {
+fn unwrap_wrappers(element1: Option<NodeRef>, element2: Option<NodeRef>,
+                   options: &DiffOptions) -> (Option<NodeRef>, Option<NodeRef>) {
+    if options.unwrap_single_child_tags.is_empty() {
+        return (element1, element2)
+    }
+    if let (Some(e1), Some(e2)) = (&element1, &element2) {
+        if let Some(child) = unwrap_single_child(e1, e2, options) {
+            return (Some(child), element2.clone())
+        }
+        if let Some(child) = unwrap_single_child(e2, e1, options) {
+            return (element1.clone(), Some(child))
+        }
+    }
+    (element1, element2)
+}
+
+// The opening tag of `node` if it's an element, with its attributes sorted by name.
+fn opening_tag(node: &NodeRef) -> Option<String> {
+    let elem = node.as_element()?;
+    let mut attributes = elem.attributes.borrow()
+                                        .map
+                                        .iter()
+                                        .map(|(k, v)| {
+                                            format!(" {}=\"{}\"",
+                                                    attribute_name(k.prefix.as_deref(), &k.local),
+                                                    v.replace('&', "&amp;").replace('"', "&quot;"))
+                                        })
+                                        .collect::<Vec<_>>();
+    attributes.sort();
+    Some(format!("<{}{}>", elem.name.local, attributes.concat()))
+}
+
+// Keeps the first `max_len` characters of `s`, followed by `...` if some were removed.
+fn truncate(s: &mut String, max_len: usize) {
+    if let Some((pos, _)) = s.char_indices().nth(max_len) {
+        s.truncate(pos);
+        s.push_str("...");
+    }
+}
+
+// Whether the tag names of the leading segments of `path` are, in order, the ones of `prefix`.
+fn has_prefix(path: &str, prefix: &[String]) -> bool {
+    let mut segments = path.split('/').skip(1);
+    prefix.iter().all(|name| {
+        segments.next().and_then(|segment| segment.split('[').next()) == Some(name.as_str())
+    })
+}
+
+// Called with each difference found and the nodes it was found on.
+type Visitor<'a> = dyn FnMut(Difference, Option<&NodeRef>, Option<&NodeRef>) -> bool + 'a;
+
+// Called with each pair of nodes the walk goes through (with `None` for a missing node), their
+// parents and the path of the node (see `AnnotatedTree::path`).
+type StepVisitor<'a> = dyn FnMut((Option<&NodeRef>, Option<&NodeRef>), (&NodeRef, &NodeRef),
+                                 &str) + 'a;
+
+/// State of a walk through two trees.
+struct Walker<'a> {
+    options: &'a DiffOptions,
+    f: &'a mut Visitor<'a>,
+    max_depth: Option<usize>,
+    reported: usize,
+    // The number of nodes visited (see `DiffOptions::progress`).
+    visited: usize,
+    // The ids of the SVG `<use>` targets being compared, to stop on reference cycles.
+    resolved_ids: Vec<String>,
+    // The start tags of the two trees as written in the source (see
+    // `DiffOptions::strict_attribute_order` and `DiffOptions::case_sensitive_names`).
+    source_tags: (Option<&'a SourceTags>, Option<&'a SourceTags>),
+    // Whether the children of two elements which only differ by their attributes are still
+    // compared (see `is_structurally_equal`).
+    descend_after_differences: bool,
+    steps: Option<&'a mut StepVisitor<'a>>,
+}
+
+impl<'a> Walker<'a> {
+    fn new(options: &'a DiffOptions, f: &'a mut Visitor<'a>) -> Walker<'a> {
+        Walker {
+            options,
+            f,
+            max_depth: options.max_depth,
+            reported: 0,
+            visited: 0,
+            resolved_ids: Vec::new(),
+            source_tags: (None, None),
+            descend_after_differences: false,
+            steps: None,
+        }
+    }
+
+    // Goes through `nodes`, children of `parents`. `segment` is the path segment of the node,
+    // if it has one.
+    fn step(&mut self, nodes: (Option<&NodeRef>, Option<&NodeRef>), parents: (&NodeRef, &NodeRef),
+            path: &[String], segment: Option<&String>) {
+        if let Some(ref mut steps) = self.steps {
+            let mut path = path.join("/");
+            if let Some(segment) = segment {
+                path.push('/');
+                path.push_str(segment);
+            }
+            steps(nodes, parents, &path);
+        }
+    }
+
+    // Reports a difference found on `nodes`, among the children of `parents`. Returns `false` if
+    // the walk has to stop.
+    fn report(&mut self, mut diff: Difference, nodes: (Option<&NodeRef>, Option<&NodeRef>),
+              parents: (&NodeRef, &NodeRef)) -> bool {
+        if self.options.max_differences.map(|max| self.reported >= max).unwrap_or(false) {
+            return false
+        }
+        if self.options.ignore_removals {
+            if let Difference::NotPresent { opposite_elem: None, .. } = diff {
+                return true
+            }
+        }
+        diff.set_parent_contents(opening_tag(parents.0), opening_tag(parents.1));
+        if !self.options.trim_path_prefix.is_empty() {
+            let prefix = &self.options.trim_path_prefix;
+            let mut elements = diff.elements_mut();
+            if elements.iter().all(|elem| has_prefix(&elem.path, prefix)) {
+                for elem in elements.iter_mut() {
+                    let path = elem.path.split('/').skip(prefix.len() + 1).collect::<Vec<_>>();
+                    elem.path = if path.is_empty() {
+                        String::new()
+                    } else {
+                        format!("/{}", path.join("/"))
+                    };
+                }
+            }
+        }
+        if let Some(ref rewriter) = self.options.path_rewriter {
+            let mut rewritten: Option<(String, String)> = None;
+            for elem in diff.elements_mut() {
+                let path = match rewritten {
+                    Some((ref original, ref path)) if *original == elem.path => path.clone(),
+                    _ => rewriter.call(&elem.path),
+                };
+                rewritten = Some((mem::replace(&mut elem.path, path.clone()), path));
+            }
+        }
+        if let Some(max_content_len) = self.options.max_content_len {
+            for elem in diff.elements_mut() {
+                truncate(&mut elem.element_content, max_content_len);
+                if let Some(ref mut parent_content) = elem.parent_content {
+                    truncate(parent_content, max_content_len);
+                }
+            }
+        }
+        self.reported += 1;
+        if !(self.f)(diff, nodes.0, nodes.1) {
+            return false
+        }
+        match self.options.max_differences {
+            Some(max_differences) => self.reported < max_differences,
+            None => true,
+        }
+    }
+
+    // Returns the `Difference::AttributeOrder` between two matching elements, if their
+    // attributes are known to be written in a different order.
+    fn attribute_order(&self, element1: &NodeRef, element2: &NodeRef,
+                       path: &[String]) -> Option<Difference> {
+        if !self.options.strict_attribute_order {
+            return None
+        }
+        let (order1, order2) = (&self.source_tags.0?.get(element1)?.attributes,
+                                &self.source_tags.1?.get(element2)?.attributes);
+        let compared = |order: &[String], other: &[String]| {
+            order.iter()
+                 .filter(|name| {
+                     other.iter().any(|o| o.eq_ignore_ascii_case(name)) &&
+                     !attributes::is_ignored(&name.to_ascii_lowercase(), self.options)
+                 })
+                 .cloned()
+                 .collect::<Vec<_>>()
+        };
+        let (compared1, compared2) = (compared(order1, order2), compared(order2, order1));
+        let lowercase = |names: &[String]| {
+            names.iter().map(|n| n.to_ascii_lowercase()).collect::<Vec<_>>()
+        };
+        if lowercase(&compared1) == lowercase(&compared2) {
+            return None
+        }
+        Some(Difference::AttributeOrder {
+            elem: ElementInformation::from_node(element1, path),
+            elem_attributes: compared1,
+            opposite_elem: ElementInformation::from_node(element2, path),
+            opposite_elem_attributes: compared2,
+        })
+    }
+
+    // Returns the difference between two matching elements whose tag or attribute names are
+    // only written with a different case (see `DiffOptions::case_sensitive_names`). The names
+    // of the difference are the ones of the source.
+    fn name_case_difference(&self, element1: &NodeRef, element2: &NodeRef,
+                            path: &[String]) -> Option<Difference> {
+        if !self.options.case_sensitive_names {
+            return None
+        }
+        let (tag1, tag2) = (self.source_tags.0?.get(element1)?,
+                            self.source_tags.1?.get(element2)?);
+        let information = |element: &NodeRef, name: &str| {
+            let mut information = ElementInformation::from_node(element, path);
+            information.element_name = name.to_owned();
+            information
+        };
+        if tag1.name != tag2.name {
+            return Some(Difference::NodeName {
+                elem: information(element1, &tag1.name),
+                opposite_elem: information(element2, &tag2.name),
+            })
+        }
+        let compared = |tag: &SourceTag| {
+            let mut names = tag.attributes.iter()
+                                          .filter(|name| {
+                                              !attributes::is_ignored(&name.to_ascii_lowercase(),
+                                                                      self.options)
+                                          })
+                                          .cloned()
+                                          .collect::<Vec<_>>();
+            names.sort_by_key(|name| name.to_ascii_lowercase());
+            names
+        };
+        if compared(tag1) == compared(tag2) {
+            return None
+        }
+        // The attributes with the names of the source.
+        let attributes = |element: &NodeRef, tag: &SourceTag| {
+            let elem = element.clone().into_element_ref()?;
+            Some(element_attributes(&elem, self.options).into_iter().map(|(name, value)| {
+                let source = tag.attributes.iter()
+                                           .find(|s| s.eq_ignore_ascii_case(&name))
+                                           .cloned();
+                (source.unwrap_or(name), value)
+            }).collect::<HashMap<_, _>>())
+        };
+        Some(Difference::NodeAttributes {
+            elem: information(element1, &tag1.name),
+            elem_attributes: attributes(element1, tag1)?,
+            opposite_elem: information(element2, &tag2.name),
+            opposite_elem_attributes: attributes(element2, tag2)?,
+        })
+    }
+
+    // Counts `count` more visited nodes.
+    fn visit(&mut self, count: usize) {
+        let visited = self.visited;
+        self.visited += count;
+        if let Some(ref progress) = self.options.progress {
+            if visited / progress.every != self.visited / progress.every {
+                progress.call(self.visited);
+            }
+        }
+    }
+
+    fn is_below_max_depth(&self, path: &[String]) -> bool {
+        match self.max_depth {
+            Some(max_depth) => get_depth(path) > max_depth,
+            None => false,
+        }
+    }
+
+    // Called instead of going through the children of `element1` and `element2` when `path` is
+    // below the maximum depth.
+    fn mark_subtree(&mut self, element1: &NodeRef, element2: &NodeRef,
+                    parents: (&NodeRef, &NodeRef), path: &mut Vec<String>) -> bool {
+        if !self.options.mark_subtrees_below_max_depth {
+            return true
+        }
+        let mut count = 0;
+        {
+            let mut counter = |_, _: Option<&NodeRef>, _: Option<&NodeRef>| {
+                count += 1;
+                true
+            };
+            let mut walker = Walker::new(self.options, &mut counter);
+            walker.max_depth = None;
+            walker.visited = self.visited;
+            walker.source_tags = self.source_tags;
+            go_through_tree(element1, element2, path, &mut walker);
+            self.visited = walker.visited;
+        }
+        if count == 0 {
+            return true
+        }
+        let parent_path = &path[..path.len() - 1];
+        self.report(Difference::SubtreeDiffers {
+            elem: ElementInformation::from_node(element1, parent_path),
+            opposite_elem: ElementInformation::from_node(element2, parent_path),
+            count,
+        }, (Some(element1), Some(element2)), parents)
+    }
+
+    // Compares the elements referenced by two SVG `<use>` elements as if they were their
+    // children (see `DiffOptions::resolve_svg_use`).
+    fn compare_use_targets(&mut self, element1: &NodeRef, element2: &NodeRef,
+                           path: &mut Vec<String>) -> bool {
+        let (id1, id2) = match (svg::use_reference(element1), svg::use_reference(element2)) {
+            (Some(id1), Some(id2)) => (id1, id2),
+            _ => return true,
+        };
+        if self.resolved_ids.contains(&id1) {
+            return true
+        }
+        let target1 = svg::find_by_id(element1, &id1);
+        let target2 = svg::find_by_id(element2, &id2);
+        let segment = match target1 {
+            Some(ref target1) if push_path_segment(target1, &mut HashMap::new(), path) => {
+                path.pop()
+            }
+            _ => None,
+        };
+        if target1.is_some() || target2.is_some() {
+            self.step((target1.as_ref(), target2.as_ref()), (element1, element2), path,
+                      segment.as_ref());
+        }
+        if let Some(diff) = compare_nodes(&target1, &target2, path, self.options) {
+            return self.report(diff, (target1.as_ref(), target2.as_ref()), (element1, element2))
+        }
+        let (target1, target2) = match (target1, target2) {
+            (Some(target1), Some(target2)) => (target1, target2),
+            // Both references are dangling.
+            _ => return true,
+        };
+        self.resolved_ids.push(id1);
+        let need_pop = push_segment(segment, path);
+        let keep_going = go_through_tree(&target1, &target2, path, self);
+        if need_pop {
+            path.pop();
+        }
+        self.resolved_ids.pop();
+        keep_going
+    }
+}
+
+// Compares two nodes found at the same place, without going through their children.
+fn compare_nodes(element1: &Option<NodeRef>, element2: &Option<NodeRef>, path: &[String],
+                 options: &DiffOptions) -> Option<Difference> {
+    match (element1, element2) {
+        (&Some(ref element1), &Some(ref element2)) => {
+            match (element1.clone().into_element_ref(), element2.clone().into_element_ref()) {
+                (Some(e1), Some(e2)) => check_elements(&e1, &e2, path, options),
+                (None, None) => {
+                    match (element1.as_text(), element2.as_text()) {
+                        (Some(t1), Some(t2)) => {
+                            if !text::texts_match_at(&t1.borrow(), &t2.borrow(), path, options) {
+                                let reported = |text: &str| {
+                                    let mut text = text.to_owned();
+                                    if let Some(max_len) = options.opaque_text_over {
+                                        truncate(&mut text, max_len);
+                                    }
+                                    text
+                                };
+                                Some(Difference::NodeText {
+                                    elem: ElementInformation::from_path(path),
+                                    elem_text: reported(&t1.borrow()),
+                                    opposite_elem: ElementInformation::from_path(path),
+                                    opposite_elem_text: reported(&t2.borrow()),
+                                })
+                            } else {
+                                None
+                            }
+                        }
+                        (None, None) => handle_other_node(element1, element2, path),
+                        _ => {
+                            Some(Difference::NodeType {
+                                elem: ElementInformation::from_node(element1, path),
+                                opposite_elem: ElementInformation::from_node(element2, path),
+                            })
+                        }
+                    }
+                }
+                _ => {
+                    Some(Difference::NodeType {
+                        elem: ElementInformation::from_node(element1, path),
+                        opposite_elem: ElementInformation::from_node(element2, path),
+                    })
+                }
+            }
+        }
+        (&Some(ref elem1), &None) => {
+            Some(Difference::NotPresent {
+                elem: Some(ElementInformation::from_node(elem1, path)),
+                opposite_elem: None,
+            })
+        }
+        (&None, &Some(ref elem2)) => {
+            Some(Difference::NotPresent {
+                elem: None,
+                opposite_elem: Some(ElementInformation::from_node(elem2, path)),
+            })
+        }
+        (&None, &None) => None,
+    }
+}
+
+// The path of `node`, in the same form as the one built when walking the trees
+// (`["", "html[0]", "head[0]", "title[0]"]`).
+fn node_path(node: &NodeRef) -> Vec<String> {
+    let mut path = node.inclusive_ancestors().filter_map(|node| {
+        let name = node.as_element()?.name.local.clone();
+        let pos = node.preceding_siblings()
+                      .filter(|s| s.as_element().map(|e| e.name.local == name).unwrap_or(false))
+                      .count();
+        Some(format!("{}[{}]", name, pos))
+    }).collect::<Vec<_>>();
+    path.push(String::new());
+    path.reverse();
+    path
+}
+
+// Compares two nodes which are neither elements nor texts (comments and processing
+// instructions are already dropped by `get_children`).
+//
+// Doctypes are compared by name and identifiers, the other kinds (document and document
+// fragment) have nothing to compare but their kind.
+fn handle_other_node(element1: &NodeRef, element2: &NodeRef,
+                     path: &[String]) -> Option<Difference> {
+    debug_assert!([element1, element2].iter().all(|e| e.as_element().is_none() &&
+                                                       e.as_text().is_none()),
+                  "elements and texts must be compared by `compare_nodes`");
+    let same = match (element1.data(), element2.data()) {
+        (NodeData::Doctype(doctype1), NodeData::Doctype(doctype2)) => {
+            doctype1.name.eq_ignore_ascii_case(&doctype2.name) &&
+            doctype1.public_id == doctype2.public_id &&
+            doctype1.system_id == doctype2.system_id
+        }
+        (NodeData::Document(_), NodeData::Document(_)) |
+        (NodeData::DocumentFragment, NodeData::DocumentFragment) => true,
+        _ => false,
+    };
+    if same {
+        None
+    } else {
+        Some(Difference::NodeType {
+            elem: ElementInformation::from_node(element1, path),
+            opposite_elem: ElementInformation::from_node(element2, path),
+        })
+    }
+}
+
+// Whether `diff`, found between `element1` and `element2`, is below
+// `DiffOptions::min_subtree_size` and mustn't be reported.
+fn is_small_subtree(diff: &Difference, element1: &Option<NodeRef>, element2: &Option<NodeRef>,
+                    options: &DiffOptions) -> bool {
+    match *diff {
+        Difference::NotPresent { .. } | Difference::NodeName { .. } => {}
+        _ => return false,
+    }
+    options.min_subtree_size > 0 &&
+    element1.iter().chain(element2.iter()).all(|element| {
+        element.descendants().filter(check_if_comment_or_empty_text).count() <
+            options.min_subtree_size
+    })
+}
+
+// Pushes `segment` if there is one, returns `true` if it did.
+fn push_segment(segment: Option<String>, path: &mut Vec<String>) -> bool {
+    match segment {
+        Some(segment) => {
+            path.push(segment);
+            true
+        }
+        None => false,
+    }
+}
+
+// Pushes the path segment of `element` if it's an element, returns `true` if it did.
+fn push_path_segment(element: &NodeRef, pos: &mut HashMap<String, usize>,
+                     path: &mut Vec<String>) -> bool {
+    if let Some(elem) = element.as_element() {
+        let pos = pos.entry((*elem.name.local).to_owned()).or_insert(0);
+        path.push(format!("{}[{}]", elem.name.local, *pos));
+        *pos += 1;
+        true
+    } else {
+        false
+    }
+}
+
+// The key matching `node` in `DiffOptions::unordered` mode: the name and the compared
+// attributes of an element (followed by the sorted keys of its children if `deep`), the
+// normalized text of a text node.
+fn unordered_key(node: &NodeRef, deep: bool, options: &DiffOptions) -> String {
+    if let Some(text) = node.as_text() {
+        return format!("#text {}", text::normalize(&text.borrow(), options))
+    }
+    let elem = match node.clone().into_element_ref() {
+        Some(elem) => elem,
+        None => return node.to_string(),
+    };
+    let mut attributes = element_attributes(&elem, options).into_iter()
+                                                            .collect::<Vec<_>>();
+    attributes.sort();
+    let mut key = format!("<{} {:?}>", elem.name.local, attributes);
+    if deep {
+        let mut children = get_children(node, options).iter()
+                                                        .map(|c| unordered_key(c, true, options))
+                                                        .collect::<Vec<_>>();
+        children.sort();
+        for child in children {
+            key.push_str(&format!("[{}]", child));
+        }
+    }
+    key
+}
+
+// Same as `go_through_tree` but the children are matched as multisets (see
+// `DiffOptions::unordered`).
+fn go_through_unordered(element1: &NodeRef, element2: &NodeRef, path: &mut Vec<String>,
+                        walker: &mut Walker) -> bool {
+    let options = walker.options;
+    let mut pos: HashMap<String, usize> = HashMap::new();
+    let mut children1 = Vec::new();
+    for child in get_children(element1, options) {
+        let need_pop = push_path_segment(&child, &mut pos, path);
+        let segment = if need_pop { path.pop() } else { None };
+        children1.push((unordered_key(&child, true, options), child, segment));
+    }
+    let mut children2 = Vec::new();
+    for child in get_children(element2, options) {
+        children2.push((unordered_key(&child, true, options), child));
+    }
+    walker.visit(cmp::max(children1.len(), children2.len()));
+    // The children with the same content on both sides, wherever they are.
+    children1.retain(|child1| {
+        match children2.iter().position(|child2| child2.0 == child1.0) {
+            Some(index) => {
+                let (_, child2) = children2.remove(index);
+                walker.step((Some(&child1.1), Some(&child2)), (element1, element2), path,
+                            child1.2.as_ref());
+                if let Some(ref hook) = options.on_match {
+                    hook.call(&child1.1, &child2, path);
+                }
+                false
+            }
+            None => true,
+        }
+    });
+    // The remaining elements with the same name and attributes are compared in order, so only
+    // the differing parts of their subtrees are reported.
+    for (_, child1, segment) in children1 {
+        let shallow_key = unordered_key(&child1, false, options);
+        let index = match segment {
+            Some(_) => children2.iter().position(|child2| {
+                child2.1.as_element().is_some() &&
+                unordered_key(&child2.1, false, options) == shallow_key
+            }),
+            None => None,
+        };
+        let keep_going = match (index, segment) {
+            (Some(index), Some(segment)) => {
+                let (_, child2) = children2.remove(index);
+                walker.step((Some(&child1), Some(&child2)), (element1, element2), path,
+                            Some(&segment));
+                if let Some(ref hook) = options.on_match {
+                    hook.call(&child1, &child2, path);
+                }
+                path.push(segment);
+                let keep_going = go_through_unordered(&child1, &child2, path, walker);
+                path.pop();
+                keep_going
+            }
+            (_, segment) => {
+                walker.step((Some(&child1), None), (element1, element2), path, segment.as_ref());
+                let diff = Difference::NotPresent {
+                    elem: Some(ElementInformation::from_node(&child1, path)),
+                    opposite_elem: None,
+                };
+                let child1 = Some(child1);
+                is_small_subtree(&diff, &child1, &None, options) ||
+                walker.report(diff, (child1.as_ref(), None), (element1, element2))
+            }
+        };
+        if !keep_going {
+            return false
+        }
+    }
+    for (_, child2) in children2 {
+        walker.step((None, Some(&child2)), (element1, element2), path, None);
+        let diff = Difference::NotPresent {
+            elem: None,
+            opposite_elem: Some(ElementInformation::from_node(&child2, path)),
+        };
+        let child2 = Some(child2);
+        if !is_small_subtree(&diff, &None, &child2, options) &&
+           !walker.report(diff, (None, child2.as_ref()), (element1, element2)) {
+            return false
+        }
+    }
+    true
+}
+
+// The key matching the `<meta>` and `<link>` elements of `<head>` (see
+// `DiffOptions::head_meta_link_unordered`).
+fn head_set_key(node: &NodeRef) -> String {
+    let elem = match node.as_element() {
+        Some(elem) => elem,
+        None => return String::new(),
+    };
+    let attributes = elem.attributes.borrow();
+    let distinguishing: &[&str] = if &*elem.name.local == "meta" {
+        &["name", "property", "http-equiv", "itemprop", "charset"]
+    } else {
+        &["rel", "href", "hreflang", "media"]
+    };
+    let mut key = elem.name.local.to_string();
+    for name in distinguishing {
+        if let Some(value) = attributes.get(*name) {
+            // `charset` is distinguished by its presence only.
+            let value = if *name == "charset" { "" } else { value };
+            key.push_str(&format!(" {}={:?}", name, value));
+        }
+    }
+    key
+}
+
+// Compares the `<meta>` and `<link>` children of two `<head>` elements as sets (see
+// `DiffOptions::head_meta_link_unordered`).
+fn go_through_head_sets(element1: &NodeRef, element2: &NodeRef, path: &[String],
+                        walker: &mut Walker) -> bool {
+    let options = walker.options;
+    let set_children = |element: &NodeRef| {
+        get_children(element, options).into_iter()
+                                      .filter(|c| is_element_named(c, &["meta", "link"]))
+                                      .collect::<Vec<_>>()
+    };
+    let (children1, mut children2) = (set_children(element1), set_children(element2));
+    walker.visit(cmp::max(children1.len(), children2.len()));
+    for child1 in children1 {
+        let key = head_set_key(&child1);
+        let child2 = children2.iter()
+                              .position(|c| head_set_key(c) == key)
+                              .map(|index| children2.remove(index));
+        let child1 = Some(child1);
+        walker.step((child1.as_ref(), child2.as_ref()), (element1, element2), path, None);
+        if let Some(diff) = compare_nodes(&child1, &child2, path, options) {
+            if !is_small_subtree(&diff, &child1, &child2, options) &&
+               !walker.report(diff, (child1.as_ref(), child2.as_ref()), (element1, element2)) {
+                return false
+            }
+        }
+    }
+    for child2 in children2 {
+        let child2 = Some(child2);
+        walker.step((None, child2.as_ref()), (element1, element2), path, None);
+        if let Some(diff) = compare_nodes(&None, &child2, path, options) {
+            if !is_small_subtree(&diff, &None, &child2, options) &&
+               !walker.report(diff, (None, child2.as_ref()), (element1, element2)) {
+                return false
+            }
+        }
+    }
+    true
+}
+
+// The key matching the `<option>` elements (see `DiffOptions::options_as_set`): their value,
+// which defaults to their text.
+fn option_key(node: &NodeRef) -> String {
+    node.as_element()
+        .and_then(|e| e.attributes.borrow().get("value").map(|value| value.to_owned()))
+        .unwrap_or_else(|| attributes::collapse_whitespace(&node.text_contents()))
+}
+
+// Compares the `<option>` children of two `<select>`, `<datalist>` or `<optgroup>` elements as
+// sets (see `DiffOptions::options_as_set`).
+fn go_through_option_sets(element1: &NodeRef, element2: &NodeRef, path: &mut Vec<String>,
+                          walker: &mut Walker) -> bool {
+    let options = walker.options;
+    let parents = (element1, element2);
+    let option_children = |element: &NodeRef| {
+        get_children(element, options).into_iter()
+                                      .filter(|c| is_element_named(c, &["option"]))
+                                      .collect::<Vec<_>>()
+    };
+    let (children1, mut children2) = (option_children(element1), option_children(element2));
+    walker.visit(cmp::max(children1.len(), children2.len()));
+    for (pos, child1) in children1.into_iter().enumerate() {
+        let key = option_key(&child1);
+        let child2 = children2.iter()
+                              .position(|c| option_key(c) == key)
+                              .map(|index| children2.remove(index));
+        let (child1, child2) = (Some(child1), child2);
+        let segment = format!("option[{}]", pos);
+        walker.step((child1.as_ref(), child2.as_ref()), parents, path, Some(&segment));
+        let keep_going = match compare_nodes(&child1, &child2, path, options) {
+            Some(diff) => {
+                is_small_subtree(&diff, &child1, &child2, options) ||
+                walker.report(diff, (child1.as_ref(), child2.as_ref()), parents)
+            }
+            None => {
+                let (child1, child2) = (child1.unwrap(), child2.unwrap());
+                if let Some(ref hook) = options.on_match {
+                    hook.call(&child1, &child2, path);
+                }
+                path.push(segment);
+                let keep_going = go_through_tree(&child1, &child2, path, walker);
+                path.pop();
+                keep_going
+            }
+        };
+        if !keep_going {
+            return false
+        }
+    }
+    for child2 in children2 {
+        let child2 = Some(child2);
+        walker.step((None, child2.as_ref()), parents, path, None);
+        if let Some(diff) = compare_nodes(&None, &child2, path, options) {
+            if !is_small_subtree(&diff, &None, &child2, options) &&
+               !walker.report(diff, (None, child2.as_ref()), parents) {
+                return false
+            }
+        }
+    }
+    true
+}
+
+// Returns `false` if the walk has to stop.
+fn go_through_tree(element1: &NodeRef, element2: &NodeRef, path: &mut Vec<String>,
+                   walker: &mut Walker) -> bool {
+    let options = walker.options;
+    if options.unordered {
+        return go_through_unordered(element1, element2, path, walker)
+    }
+    let parents = (element1, element2);
+    let head_sets = options.head_meta_link_unordered &&
+                    is_element_named(element1, &["head"]) && is_element_named(element2, &["head"]);
+    if head_sets && !go_through_head_sets(element1, element2, path, walker) {
+        return false
+    }
+    let option_parents = &["select", "datalist", "optgroup"];
+    let option_sets = options.options_as_set &&
+                      is_element_named(element1, option_parents) &&
+                      is_element_named(element2, option_parents);
+    if option_sets && !go_through_option_sets(element1, element2, path, walker) {
+        return false
+    }
+    let children = |element: &NodeRef| {
+        let mut children = get_children(element, options);
+        if head_sets {
+            children.retain(|c| !is_element_named(c, &["meta", "link"]));
+        }
+        if option_sets {
+            children.retain(|c| !is_element_named(c, &["option"]));
+        }
+        children.into_iter()
+    };
+    let mut pos: HashMap<String, usize> = HashMap::new();
+    let mut it1 = children(element1);
+    let mut it2 = children(element2);
+    loop {
+        let (element1, element2) = match unwrap_wrappers(it1.next(), it2.next(), options) {
+            (None, None) => break,
+            elements => elements,
+        };
+        walker.visit(1);
+        // The element takes its position even if it differs, so the paths of its next siblings
+        // are the ones they have in the first document.
+        let segment = match element1 {
+            Some(ref element1) if push_path_segment(element1, &mut pos, path) => path.pop(),
+            _ => None,
+        };
+        walker.step((element1.as_ref(), element2.as_ref()), parents, path, segment.as_ref());
+        // Whether the two nodes are only compared to go through their children.
+        let mut differ = false;
+        if let Some(diff) = compare_nodes(&element1, &element2, path, options) {
+            differ = true;
+            let descend = walker.descend_after_differences && !diff.is_structural() &&
+                          element1.as_ref().and_then(|e| e.as_element()).is_some() &&
+                          element2.as_ref().and_then(|e| e.as_element()).is_some();
+            if !is_small_subtree(&diff, &element1, &element2, options) &&
+               !walker.report(diff, (element1.as_ref(), element2.as_ref()), parents) {
+                return false
+            }
+            if !descend {
+                continue
+            }
+        }
+        let (element1, element2) = (element1.unwrap(), element2.unwrap());
+        if let Some(diff) = walker.name_case_difference(&element1, &element2, path) {
+            if !walker.report(diff, (Some(&element1), Some(&element2)), parents) {
+                return false
+            }
+            continue
+        }
+        if let Some(hook) = options.on_match.as_ref().filter(|_| !differ) {
+            hook.call(&element1, &element2, path);
+        }
+        if let Some(diff) = walker.attribute_order(&element1, &element2, path) {
+            if !walker.report(diff, (Some(&element1), Some(&element2)), parents) {
+                return false
+            }
+        }
+        let need_pop = push_segment(segment, path);
+        let keep_going = if need_pop && walker.is_below_max_depth(path) {
+            walker.mark_subtree(&element1, &element2, parents, path)
+        } else {
+            go_through_tree(&element1, &element2, path, walker) &&
+            (!options.resolve_svg_use || walker.compare_use_targets(&element1, &element2, path))
+        };
+        if need_pop {
+            path.pop();
+        }
+        if !keep_going {
+            return false
+        }
+    }
+    true
+}
+
+/// Take two html content strings in output, returns a `Vec` containing the differences (if any).
+pub fn get_differences(content1: &str, content2: &str) -> Vec<Difference> {
+    get_differences_with_options(content1, content2, &DiffOptions::default())
+}
+
+/// Same as `get_differences` but the comparison is configured by `options`.
+pub fn get_differences_with_options(content1: &str, content2: &str,
+                                    options: &DiffOptions) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    walk_differences(content1, content2, options, |diff| {
+        differences.push(diff);
+        true
+    });
+    differences
+}
+
+/// Same as `get_differences_with_options` but the two contents are read from `reader1` and
+/// `reader2`.
+///
+/// Fails if a reader fails (`DiffError::Io`), if a content isn't valid UTF-8
+/// (`DiffError::Utf8`) or if it is empty (`DiffError::EmptyInput`).
+///
+/// ```
+/// use html_diff::{get_differences_from_readers, DiffError, DiffOptions};
+///
+/// let options = DiffOptions::new();
+/// let differences = get_differences_from_readers(&b"<p>a</p>"[..], &b"<p>b</p>"[..], &options);
+/// assert_eq!(differences.unwrap().len(), 1);
+/// match get_differences_from_readers(&b"<p>a</p>"[..], &b""[..], &options) {
+///     Err(DiffError::EmptyInput) => {}
+///     r => panic!("unexpected result: {:?}", r),
+/// }
+/// ```
+pub fn get_differences_from_readers<R1: Read, R2: Read>(reader1: R1, reader2: R2,
+                                                        options: &DiffOptions)
+                                                        -> Result<Vec<Difference>, DiffError> {
+    fn read<R: Read>(mut reader: R) -> Result<String, DiffError> {
+        let mut buffer = Vec::new();
+        reader.read_to_end(&mut buffer)?;
+        if buffer.is_empty() {
+            return Err(DiffError::EmptyInput)
+        }
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    let (content1, content2) = (read(reader1)?, read(reader2)?);
+    Ok(get_differences_with_options(&content1, &content2, options))
+}
+
+/// Returns `Ok(())` if the two html content strings have no differences, the differences
+/// otherwise.
+///
+/// ```
+/// use html_diff::{matches_expected, Difference};
+///
+/// fn check(output: &str) -> Result<(), Vec<Difference>> {
+///     matches_expected(output, "<p>expected</p>")?;
+///     Ok(())
+/// }
+///
+/// assert!(check("<p>expected</p>").is_ok());
+/// assert_eq!(check("<p>other</p>").unwrap_err().len(), 1);
+/// ```
+pub fn matches_expected(content1: &str, content2: &str) -> Result<(), Vec<Difference>> {
+    matches_expected_with_options(content1, content2, &DiffOptions::default())
+}
+
+/// Same as `matches_expected` but the comparison is configured by `options`.
+pub fn matches_expected_with_options(content1: &str, content2: &str,
+                                     options: &DiffOptions) -> Result<(), Vec<Difference>> {
+    let differences = get_differences_with_options(content1, content2, options);
+    if differences.is_empty() {
+        Ok(())
+    } else {
+        Err(differences)
+    }
+}
+
+/// Returns `true` if the two html content strings have no structural difference (see
+/// `Difference::is_structural`), the other differences found with `options` are ignored.
+///
+/// The comparison stops at the first structural difference and the differences aren't
+/// collected, so it's a cheap check for a CI gate. `DiffOptions::max_differences` isn't taken
+/// into account. The children of two elements which only differ by their attributes are still
+/// compared.
+///
+/// ```
+/// use html_diff::{is_structurally_equal, DiffOptions};
+///
+/// let options = DiffOptions::new();
+/// assert!(is_structurally_equal("<p class=\"a\">Some text</p>", "<p>Other  text</p>",
+///                               &options));
+/// assert!(!is_structurally_equal("<p>text</p>", "<div>text</div>", &options));
+/// ```
+pub fn is_structurally_equal(content1: &str, content2: &str, options: &DiffOptions) -> bool {
+    let mut options = options.clone();
+    options.max_differences = None;
+    let mut equal = true;
+    walk_sources(content1, content2, &options, true, &mut |diff, _, _| {
+        equal = !diff.is_structural();
+        equal
+    });
+    equal
+}
+
+/// Calls `f` on each difference found between the two html content strings, in document order.
+///
+/// The walk stops as soon as `f` returns `false`.
+pub fn walk_differences<F>(content1: &str, content2: &str, options: &DiffOptions, mut f: F)
+    where F: FnMut(Difference) -> bool
+{
+    walk_sources(content1, content2, options, false,
+                 &mut |diff, _: Option<&NodeRef>, _: Option<&NodeRef>| f(diff));
+}
+
+/// Same as `walk_differences` but `f` is also given the nodes each difference was found on:
+/// the two elements or texts compared, or the only one there is for a
+/// `Difference::NotPresent` (`None` on the missing side). For a `Difference::SubtreeDiffers`,
+/// they are the roots of the subtrees. The walk only stops at `DiffOptions::max_differences`.
+///
+/// The nodes belong to the trees parsed for the walk, which are dropped when it ends. They can
+/// be cloned to be kept (a `NodeRef` is reference-counted), but a node only keeps its
+/// descendants alive: once the walk is over, its ancestors and siblings are gone (`parent()`
+/// returns `None`). The trees mustn't be modified during the walk (the attributes can be
+/// borrowed for reading but not mutably), since they are still being gone through.
+///
+/// ```
+/// use html_diff::{walk_differences_with_nodes, DiffOptions};
+///
+/// let mut parents = Vec::new();
+/// walk_differences_with_nodes("<ul class=\"menu\"><li>a</li></ul>",
+///                             "<ul class=\"menu\"><li>b</li></ul>", &DiffOptions::new(),
+///                             |_, node, _| {
+///     // The class of the `<ul>` above the text which differs.
+///     let ul = node.and_then(|n| n.ancestors().nth(1)).unwrap();
+///     let elem = ul.as_element().unwrap();
+///     parents.push(elem.attributes.borrow().get("class").unwrap().to_owned());
+/// });
+/// assert_eq!(parents, ["menu"]);
+/// ```
+pub fn walk_differences_with_nodes<F>(content1: &str, content2: &str, options: &DiffOptions,
+                                      mut f: F)
+    where F: FnMut(&Difference, Option<&NodeRef>, Option<&NodeRef>)
+{
+    walk_sources(content1, content2, options, false, &mut |diff, node1, node2| {
+        f(&diff, node1, node2);
+        true
+    });
+}
+
+// Goes through the trees of the two html content strings. With `descend_after_differences`,
+// the children of two elements which only differ by their attributes are still compared.
+fn walk_sources(content1: &str, content2: &str, options: &DiffOptions,
+                descend_after_differences: bool, f: &mut Visitor) {
+    let (side1, side2) = (Side::parse(content1, options), Side::parse(content2, options));
+    let mut walker = Walker::new(options, f);
+    walker.descend_after_differences = descend_after_differences;
+    walk_sides(&side1, &side2, &mut walker);
+}
+
+// One of the two contents of a comparison, set up as the options ask.
+struct Side {
+    // The node the walk starts from (the `<svg>` element around the content with
+    // `DiffOptions::xml_syntax`, the document otherwise).
+    root: NodeRef,
+    // The start tags of the source, if the options need them.
+    source_tags: Option<SourceTags>,
+    // Whether the source is a fragment (see `DiffOptions::auto_unwrap`).
+    fragment: bool,
+}
+
+impl Side {
+    fn parse(content: &str, options: &DiffOptions) -> Side {
+        let mut side = Side::from_tree(&parse_document_with_options(content, options), options);
+        side.fragment = warnings::is_fragment(content);
+        if options.strict_attribute_order || options.case_sensitive_names {
+            let content = if options.xml_syntax {
+                Cow::Owned(format!("<svg>{}</svg>", content))
+            } else {
+                Cow::Borrowed(content)
+            };
+            side.source_tags = Some(SourceTags::new(&content, &side.root));
+        }
+        side
+    }
+
+    // The tree has no source: it is a fragment if it has no doctype.
+    fn from_tree(tree: &NodeRef, options: &DiffOptions) -> Side {
+        let root = if options.xml_syntax {
+            tree.select_first("svg").map(|svg| svg.as_node().clone())
+                                    .unwrap_or_else(|_| tree.clone())
+        } else {
+            tree.clone()
+        };
+        Side {
+            root,
+            source_tags: None,
+            fragment: !tree.children().any(|child| child.as_doctype().is_some()),
+        }
+    }
+}
+
+// Returns the nodes the walk through the two sides starts from.
+fn walk_roots(side1: &Side, side2: &Side, options: &DiffOptions) -> (NodeRef, NodeRef) {
+    if options.auto_unwrap && !options.xml_syntax && side1.fragment && side2.fragment {
+        if let (Some(body1), Some(body2)) = (fragment_body(&side1.root),
+                                             fragment_body(&side2.root)) {
+            return (body1, body2)
+        }
+    }
+    (side1.root.clone(), side2.root.clone())
+}
+
+// Goes through the two sides with `walker`.
+fn walk_sides<'a>(side1: &'a Side, side2: &'a Side, walker: &mut Walker<'a>) {
+    let (root1, root2) = walk_roots(side1, side2, walker.options);
+    walker.source_tags = (side1.source_tags.as_ref(), side2.source_tags.as_ref());
+    go_through_tree(&root1, &root2, &mut vec![String::new()], walker);
+}
+
+// Returns the `<body>` the parser added around a fragment, unless the fragment also put
+// elements in `<head>` (like a `<title>`).
+fn fragment_body(tree: &NodeRef) -> Option<NodeRef> {
+    let head = tree.select_first("head").ok()?;
+    if head.as_node().children().next().is_some() {
+        return None
+    }
+    tree.select_first("body").ok().map(|body| body.as_node().clone())
+}
+
+/// Parses an html content string the same way the comparison functions do.
+pub fn parse_document(content: &str) -> NodeRef {
+    kuchiki::parse_html().one(content)
+}
+
+/// Same as `parse_document` but the content is parsed the way the comparison functions do
+/// with `options`: with `DiffOptions::xml_syntax`, it is parsed in an `<svg>` element (which
+/// the returned document has around it).
+///
+/// ```
+/// use html_diff::{get_differences_against_with_options, parse_document_with_options};
+/// use html_diff::DiffOptions;
+///
+/// let options = DiffOptions::new().xml_syntax(true);
+/// let reference = parse_document_with_options("<item/><item/>", &options);
+/// assert!(get_differences_against_with_options(&reference, "<item/><item/>",
+///                                              &options).is_empty());
+/// ```
+pub fn parse_document_with_options(content: &str, options: &DiffOptions) -> NodeRef {
+    if options.xml_syntax {
+        // In the foreign content of an `<svg>`, the tags are parsed the XML way.
+        parse_document(&format!("<svg>{}</svg>", content))
+    } else {
+        parse_document(content)
+    }
+}
+
+/// Same as `get_differences` but compares an already parsed tree, so the same reference can be
+/// compared to many candidates without being parsed again.
+///
+/// ```
+/// use html_diff::{get_differences_against, parse_document};
+///
+/// let reference = parse_document("<p>expected</p>");
+/// assert!(get_differences_against(&reference, "<p>expected</p>").is_empty());
+/// assert_eq!(get_differences_against(&reference, "<p>other</p>").len(), 1);
+/// ```
+pub fn get_differences_against(reference: &NodeRef, candidate: &str) -> Vec<Difference> {
+    get_differences_against_with_options(reference, candidate, &DiffOptions::default())
+}
+
+/// Same as `get_differences_against` but the comparison is configured by `options`, as with
+/// `get_differences_with_options`. Since the reference has no source:
+///
+/// * it is a fragment (see `DiffOptions::auto_unwrap`) if it has no doctype,
+/// * with `DiffOptions::xml_syntax`, it must be parsed with `parse_document_with_options`,
+/// * the differences which need the sources of both contents (see
+///   `DiffOptions::strict_attribute_order` and `DiffOptions::case_sensitive_names`) aren't
+///   found.
+pub fn get_differences_against_with_options(reference: &NodeRef, candidate: &str,
+                                            options: &DiffOptions) -> Vec<Difference> {
+    let mut differences = Vec::new();
+    {
+        let mut push = |diff, _: Option<&NodeRef>, _: Option<&NodeRef>| {
+            differences.push(diff);
+            true
+        };
+        let (side1, side2) = (Side::from_tree(reference, options),
+                              Side::parse(candidate, options));
+        walk_sides(&side1, &side2, &mut Walker::new(options, &mut push));
+    }
+    differences
+}
+
+/// Returns an overview of the differences: the differences down to the depth `levels` (see
+/// `ElementInformation::depth`), and a `Difference::SubtreeDiffers` with the number of
+/// differences below for each deeper subtree which differs.
+///
+/// It is the same as `get_differences_with_options` with `DiffOptions::max_depth(levels)` and
+/// `DiffOptions::mark_subtrees_below_max_depth(true)`.
+pub fn shallow_differences(content1: &str, content2: &str, levels: usize) -> Vec<Difference> {
+    let options = DiffOptions::new().max_depth(levels).mark_subtrees_below_max_depth(true);
+    get_differences_with_options(content1, content2, &options)
+}
+
+/// Compares only the elements matching `predicate`: the matching elements of each document are
+/// taken in document order, wherever they are, and compared one by one (with their subtrees).
+/// A `Difference::NotPresent` is reported for each element without counterpart when the two
+/// documents don't have the same number of matching elements.
+///
+/// The paths are the ones of the elements in their documents. If matching elements are nested,
+/// the differences inside the inner ones are reported once for each of them.
+///
+/// ```
+/// use html_diff::get_differences_filtered;
+///
+/// let is_img = |node: &html_diff::NodeRef| {
+///     node.as_element().map(|e| &*e.name.local == "img").unwrap_or(false)
+/// };
+/// assert!(get_differences_filtered("<p><img src=\"a.png\">a</p>",
+///                                  "<div><img src=\"a.png\"></div>",
+///                                  is_img).is_empty());
+/// assert_eq!(get_differences_filtered("<img src=\"a.png\">", "<img src=\"b.png\">",
+///                                     is_img).len(), 1);
+/// ```
+pub fn get_differences_filtered<F>(content1: &str, content2: &str,
+                                   predicate: F) -> Vec<Difference>
+    where F: Fn(&NodeRef) -> bool
+{
+    let options = DiffOptions::default();
+    let (tree1, tree2) = (parse_document(content1), parse_document(content2));
+    let matching = |tree: &NodeRef| {
+        tree.descendants().filter(|node| node.as_element().is_some() && predicate(node))
+                          .collect::<Vec<_>>()
+    };
+    let (elements1, elements2) = (matching(&tree1), matching(&tree2));
+    let mut differences = Vec::new();
+    {
+        let mut push = |diff, _: Option<&NodeRef>, _: Option<&NodeRef>| {
+            differences.push(diff);
+            true
+        };
+        let mut walker = Walker::new(&options, &mut push);
+        for i in 0..cmp::max(elements1.len(), elements2.len()) {
+            let (element1, element2) = (elements1.get(i).cloned(), elements2.get(i).cloned());
+            let mut path = node_path(element1.as_ref().or(element2.as_ref())
+                                             .expect("no matching element"));
+            let parent = |element: &Option<NodeRef>, other: &Option<NodeRef>| {
+                element.as_ref().or(other.as_ref()).and_then(|e| e.parent())
+                       .expect("element without parent")
+            };
+            let parents = (parent(&element1, &element2), parent(&element2, &element1));
+            let parent_path = &path[..path.len() - 1];
+            if let Some(diff) = compare_nodes(&element1, &element2, parent_path, &options) {
+                walker.report(diff, (element1.as_ref(), element2.as_ref()),
+                              (&parents.0, &parents.1));
+                continue
+            }
+            let (element1, element2) = (element1.unwrap(), element2.unwrap());
+            go_through_tree(&element1, &element2, &mut path, &mut walker);
+        }
+    }
+    differences
+}
+
+/// Returns the first difference found between the two html content strings, or `None` if they
+/// are equal.
+///
+/// Cheaper than `get_differences_with_options` since the walk stops at the first difference.
+pub fn first_difference(content1: &str, content2: &str,
+                        options: &DiffOptions) -> Option<Difference> {
+    let mut first = None;
+    walk_differences(content1, content2, options, |diff| {
+        first = Some(diff);
+        false
+    });
+    first
+}
+
+// Counts the nodes compared below `node` (so without comments or empty texts).
+fn count_nodes(node: &NodeRef, options: &DiffOptions) -> usize {
+    get_children(node, options).iter().map(|c| 1 + count_nodes(c, options)).sum()
+}
+
+/// Returns the number of nodes of the html content string compared with `options` (so
+/// without the comments, the empty texts and the dropped elements, and only below the `<svg>`
+/// or `<body>` the comparison starts from with `DiffOptions::xml_syntax` or
+/// `DiffOptions::auto_unwrap`). Comparing it with another content visits at most as many nodes
+/// as the biggest of the two counts (see `DiffOptions::progress`).
+///
+/// ```
+/// use html_diff::{count_compared_nodes, DiffOptions};
+///
+/// // `html`, `head`, `body`, `p`, its text and the `b` with its text.
+/// assert_eq!(count_compared_nodes("<p>a <b>b</b><!-- c --></p>", &DiffOptions::new()), 7);
+/// ```
+pub fn count_compared_nodes(content: &str, options: &DiffOptions) -> usize {
+    let side = Side::parse(content, options);
+    count_nodes(&walk_roots(&side, &side, options).0, options)
+}
+
+/// Returns how similar the two html content strings are, from `0.` (completely different) to
+/// `1.` (equal).
+///
+/// The score is `1 - sum(weight(kind)) / (nodes * max_weight)` (clamped to `0.`), where the sum
+/// goes over all differences found (a `Difference::SubtreeDiffers` counts as many times as the
+/// number of differences it stands for), `nodes` is the number of nodes compared in the biggest
+/// of the two documents and `max_weight` is the highest weight. The weights are set with
+/// `DiffOptions::similarity_weights` and are all `1.` by default, in which case the score is
+/// simply `1 - differences / nodes`.
+pub fn similarity_score(content1: &str, content2: &str, options: &DiffOptions) -> f64 {
+    let (side1, side2) = (Side::parse(content1, options), Side::parse(content2, options));
+    let mut weights = 0.;
+    {
+        let mut add = |diff, _: Option<&NodeRef>, _: Option<&NodeRef>| {
+            let count = match diff {
+                Difference::SubtreeDiffers { count, .. } => count,
+                _ => 1,
+            };
+            weights += options.similarity_weight(diff.kind()) * count as f64;
+            true
+        };
+        walk_sides(&side1, &side2, &mut Walker::new(options, &mut add));
+    }
+    let (root1, root2) = walk_roots(&side1, &side2, options);
+    let nodes = cmp::max(count_nodes(&root1, options), count_nodes(&root2, options));
+    let max_weight = DifferenceKind::all().iter()
+                                          .map(|k| options.similarity_weight(*k))
+                                          .fold(0., f64::max);
+    if weights == 0. {
+        1.
+    } else if nodes == 0 || max_weight <= 0. {
+        0.
+    } else {
+        (1. - weights / (nodes as f64 * max_weight)).max(0.)
+    }
+}
+
+/// Returns `true` if `candidate` has no differences with at least one of the `expected`
+/// variants.
+///
+/// ```
+/// use html_diff::matches_any;
+///
+/// let variants = ["<button>Buy</button>", "<button>Buy now</button>"];
+/// assert!(matches_any("<button>Buy now</button>", &variants));
+/// assert!(!matches_any("<button>Sell</button>", &variants));
+/// ```
+pub fn matches_any(candidate: &str, expected: &[&str]) -> bool {
+    matches_any_with_options(candidate, expected, &DiffOptions::default())
+}
+
+/// Same as `matches_any` but the comparison is configured by `options`.
+pub fn matches_any_with_options(candidate: &str, expected: &[&str],
+                                options: &DiffOptions) -> bool {
+    expected.iter().any(|variant| first_difference(candidate, variant, options).is_none())
+}
+
+/// Returns the position in `expected` of the variant closest to `candidate` (the first one
+/// without differences, otherwise the one with the highest `similarity_score`) with the
+/// differences between `candidate` and it. Returns `None` if `expected` is empty.
+///
+/// ```
+/// use html_diff::{closest_variant, DiffOptions};
+///
+/// let variants = ["<p>a</p><p>b</p>", "<p>a</p><p>c</p><p>d</p>"];
+/// let (index, differences) = closest_variant("<p>a</p><p>e</p>", &variants,
+///                                            &DiffOptions::default()).unwrap();
+/// assert_eq!(index, 0);
+/// assert_eq!(differences.len(), 1);
+/// ```
+pub fn closest_variant(candidate: &str, expected: &[&str],
+                       options: &DiffOptions) -> Option<(usize, Vec<Difference>)> {
+    if let Some(index) = expected.iter()
+                                 .position(|v| first_difference(candidate, v, options).is_none()) {
+        return Some((index, Vec::new()))
+    }
+    let mut best: Option<(usize, f64)> = None;
+    for (index, variant) in expected.iter().enumerate() {
+        let score = similarity_score(candidate, variant, options);
+        if best.map(|(_, best_score)| score > best_score).unwrap_or(true) {
+            best = Some((index, score));
+        }
+    }
+    best.map(|(index, _)| {
+        (index, get_differences_with_options(candidate, expected[index], options))
+    })
+}
+
+/// Compares only the text content of the two html content strings, returns a
+/// `Difference::NodeText` (with an empty path) if it differs.
+///
+/// This intentionally ignores the whole markup: tags, attributes and the way the text is split
+/// between elements don't matter, only the concatenated text (with its whitespace normalized)
+/// is compared. It is a fast way to check if the visible text changed before running a full
+/// comparison.
+///
+/// ```
+/// use html_diff::text_only_differences;
+///
+/// assert!(text_only_differences("<p>a <b>b</b></p>", "<div>a\n b</div>").is_none());
+/// assert!(text_only_differences("<p>a</p>", "<p>b</p>").is_some());
+/// ```
+pub fn text_only_differences(content1: &str, content2: &str) -> Option<Difference> {
+    let options = DiffOptions::new().normalize_whitespace(true);
+    let text1 = parse_document(content1).text_contents();
+    let text2 = parse_document(content2).text_contents();
+    if text::texts_match(&text1, &text2, &options) {
+        None
+    } else {
+        Some(Difference::NodeText {
+            elem: ElementInformation::from_path(&[]),
+            elem_text: text::normalize(&text1, &options),
+            opposite_elem: ElementInformation::from_path(&[]),
+            opposite_elem_text: text::normalize(&text2, &options),
+        })
+    }
+}
+
+/// Returns the `(path, old_text, new_text)` of each `Difference::NodeText` found between the two
+/// html content strings, leaving out the structural differences. The whitespace is normalized
+/// both for the comparison and in the returned texts, so the texts only differing by their
+/// whitespace aren't returned.
+///
+/// ```
+/// use html_diff::text_differences;
+///
+/// let texts = text_differences("<h1>Hello</h1><p class=\"a\">Good  bye</p>",
+///                              "<h1>Hi</h1>\n<p>Good bye</p><img>");
+/// assert_eq!(texts, vec![("/html[0]/body[0]/h1[0]".to_owned(), "Hello".to_owned(),
+///                         "Hi".to_owned())]);
+/// ```
+pub fn text_differences(content1: &str, content2: &str) -> Vec<(String, String, String)> {
+    let options = DiffOptions::new().normalize_whitespace(true);
+    get_differences_with_options(content1, content2, &options).into_iter().filter_map(|diff| {
+        match diff {
+            Difference::NodeText { elem, elem_text, opposite_elem_text, .. } => {
+                Some((elem.path,
+                      text::normalize(&elem_text, &options),
+                      text::normalize(&opposite_elem_text, &options)))
+            }
+            _ => None,
+        }
+    }).collect()
+}
+
+/// Prefixes the paths of all the elements of `differences` with `prefix` (updating their
+/// depth), so the differences found by comparing parts of documents separately can be put
+/// together in one report.
+///
+/// ```
+/// use html_diff::{get_differences, merge_with_prefix};
+///
+/// let differences = merge_with_prefix(get_differences("<p>a</p>", "<p>b</p>"), "/main[0]");
+/// assert_eq!(differences[0].path(), "/main[0]/html[0]/body[0]/p[0]");
+/// ```
+pub fn merge_with_prefix(mut differences: Vec<Difference>, prefix: &str) -> Vec<Difference> {
+    let prefix = prefix.trim_end_matches('/');
+    for diff in &mut differences {
+        for elem in diff.elements_mut() {
+            elem.path = format!("{}/{}", prefix, elem.path.trim_start_matches('/'))
+                            .trim_end_matches('/')
+                            .to_owned();
+            elem.depth = elem.path_segments().len();
+        }
+    }
+    differences
+}
+
+/// Returns the path of the nearest common ancestor of two differences, the longest path
+/// (compared by segments, see `ElementInformation::path_segments`) both of their paths start
+/// with. It is `""` (the document) if they have no segment in common.
+///
+/// ```
+/// use html_diff::{common_ancestor_path, get_differences};
+///
+/// let differences = get_differences("<main><p>a</p><ul><li>b</li></ul></main><p>c</p>",
+///                                   "<main><p>x</p><ul><li>y</li></ul></main><p>z</p>");
+/// assert_eq!(common_ancestor_path(&differences[0], &differences[1]), "/html[0]/body[0]/main[0]");
+/// assert_eq!(common_ancestor_path(&differences[0], &differences[2]), "/html[0]/body[0]");
+/// ```
+pub fn common_ancestor_path(a: &Difference, b: &Difference) -> String {
+    let (segments1, segments2) = (a.elem_information().path_segments(),
+                                  b.elem_information().path_segments());
+    segments1.iter()
+             .zip(segments2.iter())
+             .take_while(|&(segment1, segment2)| segment1 == segment2)
+             .map(|(segment, _)| format!("/{}", segment))
+             .collect()
+}
+
+/// The differences found for one of the pairs given to `diff_many`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffResult {
+    /// The position of the pair in the slice given to `diff_many`.
+    pub index: usize,
+    pub differences: Vec<Difference>,
+    /// The warnings about the comparison of this pair (see `get_warnings`).
+    pub warnings: Vec<DiffWarning>,
+}
+
+impl DiffResult {
+    /// Returns `true` if no difference was found.
+    pub fn is_equal(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// Returns the number of differences of the given kind.
+    pub fn count(&self, kind: DifferenceKind) -> usize {
+        self.differences.iter().filter(|d| d.kind() == kind).count()
+    }
+}
+
+/// Compares each pair of html content strings, returns one `DiffResult` per pair (in the same
+/// order).
+pub fn diff_many(pairs: &[(String, String)]) -> Vec<DiffResult> {
+    pairs.iter()
+         .enumerate()
+         .map(|(index, (content1, content2))| {
+             DiffResult {
+                 index,
+                 differences: get_differences(content1, content2),
+                 warnings: get_warnings(content1, content2),
+             }
+         })
+         .collect()
+}
+
+/// Asserts that two html content strings have no differences.
+///
+/// On failure, panics with the list of the differences found (one per line). A `DiffOptions`
+/// can be given as third argument to configure the comparison.
+///
+/// ```should_panic
+/// #[macro_use]
+/// extern crate html_diff;
+///
+/// # fn main() {
+/// assert_html_eq!("<div><p>a</p></div>", "<div><p>b</p></div>");
+/// # }
+/// ```
+#[macro_export]
+macro_rules! assert_html_eq {
+    ($left:expr, $right:expr) => {
+        assert_html_eq!($left, $right, &$crate::DiffOptions::default())
+    };
+    ($left:expr, $right:expr, $options:expr) => {
+        {
+            let differences = $crate::get_differences_with_options(&$left, &$right, $options);
+            if !differences.is_empty() {
+                let mut message = format!("HTML contents differ ({} difference(s)):",
+                                          differences.len());
+                for diff in &differences {
+                    message.push_str(&format!("\n    {}", diff));
+                }
+                panic!("{}", message);
+            }
+        }
+    };
+}
+
+#[test]
+fn basic_diff() {
+    let original = "<div><foo></foo></div>";
+    let other = "<div><p></p></div>";
+
+    let differences = get_differences(original, other);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    assert_eq!(differences[0].is_node_name(), true, "{:?}", differences[0]);
+}
+
+// Test if we stop correctly at first difference and don't go down.
+#[test]
+fn children_diff() {
     let original = "<div><foo><p></p></foo></div>";
     let other = "<div><p><t></t></p></div>";
 
-    let differences = get_differences(original, other);
-    assert_eq!(differences.len(), 1, "{:?}", differences);
-    assert_eq!(differences[0].is_node_name(), true, "{:?}", differences[0]);
+    let differences = get_differences(original, other);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    assert_eq!(differences[0].is_node_name(), true, "{:?}", differences[0]);
+}
+
+#[test]
+fn check_attributes_order() {
+    let original = "<div id=\"g\" class=\"foo\"><foo><p></p></foo></div>";
+    let other = "<div class=\"foo\" id=\"g\"><foo><p></p></foo></div>";
+
+    let differences = get_differences(original, other);
+    assert_eq!(differences.len(), 0, "{:?}", differences);
+}
+
+#[test]
+fn check_attributes_missing() {
+    let original = "<div id=\"g\" class=\"foo\"><foo><p></p></foo></div>";
+    let other = "<div class=\"foo\"><foo><p></p></foo></div>";
+
+    let differences = get_differences(original, other);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    assert_eq!(differences[0].is_node_attributes(), true, "{:?}", differences[0]);
+    match differences[0] {
+        Difference::NodeAttributes { ref elem_attributes,
+                                     ref opposite_elem_attributes,
+                                     .. } => {
+            let mut attributes = HashMap::new();
+            attributes.insert("class".to_owned(), "foo".to_owned());
+            assert_eq!(attributes, *opposite_elem_attributes,
+                       "{:?}/{:?}", opposite_elem_attributes, attributes);
+            attributes.insert("id".to_owned(), "g".to_owned());
+            assert_eq!(attributes, *elem_attributes,
+                       "{:?}/{:?}", elem_attributes, attributes);
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn check_child_below() {
+    let original = "<div><foo></foo><a></a><b><c></c></b></div>";
+    let other = "<div><foo></foo><a></a><b><c><d></d></c></b></div>";
+
+    let differences = get_differences(original, other);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    assert_eq!(differences[0].is_not_present(), true, "{:?}", differences[0]);
+}
+
+#[test]
+fn test_path() {
+    let original = "<div><foo></foo><a></a><b><c></c></b></div>";
+    let other = "<div><foo></foo><a></a><b><c><d></d></c></b></div>";
+
+    let differences = get_differences(original, other);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    assert_eq!(differences[0].is_not_present(), true, "{:?}", differences[0]);
+    match differences[0] {
+        Difference::NotPresent { ref elem, ref opposite_elem } => {
+            assert_eq!(elem.is_none(), true, "{:?}", elem);
+            assert_eq!(opposite_elem.is_some(), true, "{:?}", opposite_elem);
+            let mut expected = ElementInformation::new("d", "<d></d>",
+                                                       "/html[0]/body[0]/div[0]/b[0]/c[0]");
+            expected.parent_content = Some("<c>".to_owned());
+            assert_eq!(*opposite_elem, Some(expected), "{:?}", opposite_elem);
+        }
+        _ => unreachable!(),
+    }
+    assert_eq!(differences[0].is_not_present(), true, "{:?}", differences[0]);
+}
+
+#[test]
+fn test_issue_6() {
+    let a = "<!-- .basic -->\n<span class=\"why\">chunky bacon</span>\n\n<!-- .emphasis -->\n\
+             <em>chunky bacon</em>\n\n<!-- .emphasis_with_role -->\n<em class=\"why\">chunky bacon\
+             </em>\n\n<!-- .strong -->\n<strong>chunky bacon</strong>\n\n<!-- .strong_with_role \
+             -->\n<strong class=\"why\">chunky bacon</strong>\n\n<!-- .monospaced -->\n<code>\
+             hello world!</code>\n\n<!-- .monospaced_with_role -->\n<code class=\"why\">hello \
+             world!</code>\n\n<!-- .superscript -->\n<sup>super</sup>chunky bacon\n\n<!-- \
+             .superscript_with_role -->\n<sup class=\"why\">super</sup>chunky bacon\n\n<!-- \
+             .subscript -->\n<sub>sub</sub>chunky bacon\n\n<!-- .subscript_with_role -->\n<sub \
+             class=\"why\">sub</sub>chunky bacon\n\n<!-- .mark -->\n<mark>chunky bacon</mark>\n\n\
+             <!-- .double -->\n“chunky bacon”\n\n<!-- .double_with_role -->\n<span class=\"why\">\
+             “chunky bacon”</span>\n\n<!-- .single -->\n‘chunky bacon’\n\n<!-- .single_with_role \
+             -->\n<span class=\"why\">‘chunky bacon’</span>\n\n<!-- .asciimath -->\n\\$sqrt(4) = 2\
+             \\$\n\n<!-- .latexmath -->\n\\($C = \\alpha + \\beta Y^{\\gamma} + \\epsilon$\\)\n\n\
+             <!-- .with_id -->\n<a id=\"why\"></a><em>chunky bacon</em>\n\n<!-- \
+             .mixed_monospace_bold_italic -->\n<code><strong><em>monospace bold italic phrase</em>\
+             </strong></code> and le<code><strong><em>tt</em></strong></code>ers\n";
+    let b = "<mark></mark><div class=\"paragraph\"><p>chunky bacon</p></div><mark></mark>";
+    let _ = get_differences(a, b);
+}
+
+#[test]
+fn test_attributes() {
+    let a = r#"<span class="toto"></span>"#;
+    let b = r#"<span class="tata"></span>"#;
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 1);
+
+    let a = r#"<span foo="a" class="toto"></span>"#;
+    let b = r#"<span class="toto" foo="a"></span>"#;
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 0);
+}
+
+#[test]
+fn test_path_logic() {
+    use Difference::NodeText;
+
+    let a = r#"<div>
+    <img src="...">
+    <div>
+        <p>here will be changed content</p>
+    </div>
+</div>"#;
+    let b = r#"<div>
+    <img src="...">
+    <div>
+        <p>here will be changed</p>
+    </div>
+</div>"#;
+
+    let differences = get_differences(a, b);
+    assert_eq!(differences,
+               vec![NodeText {
+                        elem: ElementInformation {
+                                  element_name: String::new(),
+                                  element_content: String::new(),
+                                  path: "/html[0]/body[0]/div[0]/div[0]/p[0]".to_owned(),
+                                  depth: 5,
+                                  parent_content: Some("<p>".to_owned()),
+                              },
+                        elem_text: "here will be changed content".to_owned(),
+                        opposite_elem: ElementInformation {
+                                           element_name: String::new(),
+                                           element_content: String::new(),
+                                           path: "/html[0]/body[0]/div[0]/div[0]/p[0]".to_owned(),
+                                           depth: 5,
+                                           parent_content: Some("<p>".to_owned()),
+                                       },
+                        opposite_elem_text: "here will be changed".to_owned(),
+                    }]);
+}
+
+#[test]
+fn test_path_logic2() {
+    use Difference::NodeText;
+
+    let a = r#"<div>
+    <div></div>
+    <div>
+        <p>here will be changed content</p>
+    </div>
+</div>"#;
+    let b = r#"<div>
+    <div></div>
+    <div>
+        <p>here will be changed</p>
+    </div>
+</div>"#;
+
+    let differences = get_differences(a, b);
+    assert_eq!(differences,
+               vec![NodeText {
+                        elem: ElementInformation {
+                                  element_name: String::new(),
+                                  element_content: String::new(),
+                                  path: "/html[0]/body[0]/div[0]/div[1]/p[0]".to_owned(),
+                                  depth: 5,
+                                  parent_content: Some("<p>".to_owned()),
+                              },
+                        elem_text: "here will be changed content".to_owned(),
+                        opposite_elem: ElementInformation {
+                                           element_name: String::new(),
+                                           element_content: String::new(),
+                                           path: "/html[0]/body[0]/div[0]/div[1]/p[0]".to_owned(),
+                                           depth: 5,
+                                           parent_content: Some("<p>".to_owned()),
+                                       },
+                        opposite_elem_text: "here will be changed".to_owned(),
+                    }]);
+}
+
+#[test]
+fn test_unicode() {
+    let a = r#"<div><div></div>០</div<"#;
+    let b = r#"<div><div></div>០</div>"#;
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 0);
+}
+
+#[test]
+fn test_collapse_text_nodes() {
+    let a = "<div>a&amp;b</div>";
+    let b = "<div>a<!-- split -->&amp;b</div>";
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 0, "{:?}", differences);
+
+    let a = "<div>a&amp;b<!-- split -->c</div>";
+    let b = "<div>a<!-- other split -->&amp;bc</div>";
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 0, "{:?}", differences);
+
+    let differences = get_differences_with_options(a, b,
+                                                   &DiffOptions::new().collapse_text_nodes(false));
+    assert_eq!(differences.len(), 2, "{:?}", differences);
+    assert!(differences[0].is_node_text(), "{:?}", differences[0]);
+}
+
+#[test]
+fn test_first_difference() {
+    let a = "<div><p>a</p><b></b></div>";
+    let b = "<div><p>b</p><i></i></div>";
+    let options = DiffOptions::new();
+    let first = first_difference(a, b, &options);
+    assert_eq!(first.as_ref().map(|d| d.is_node_text()), Some(true), "{:?}", first);
+    assert_eq!(first, get_differences(a, b).into_iter().next());
+    assert_eq!(first_difference(a, a, &options), None);
+}
+
+#[test]
+fn test_assert_html_eq() {
+    assert_html_eq!("<div id=\"a\" class=\"b\"></div>", "<div class=\"b\" id=\"a\"></div>");
+    assert_html_eq!(String::from("<p>a</p>"), "<p>a<!-- comment --></p>");
+    assert_html_eq!("<p>a</p>b", "<p>a</p><!-- c -->b", &DiffOptions::new());
+}
+
+#[test]
+#[should_panic(expected = "[Texts differ]")]
+fn test_assert_html_eq_failure() {
+    assert_html_eq!("<p>a</p>", "<p>b</p>");
+}
+
+#[test]
+fn test_cdata() {
+    let a = "<div><![CDATA[a < b]]></div>";
+    let b = "<div><![CDATA[a & b]]></div>";
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    match differences[0] {
+        Difference::NodeText { ref elem_text, ref opposite_elem_text, .. } => {
+            assert_eq!(elem_text, "a < b");
+            assert_eq!(opposite_elem_text, "a & b");
+        }
+        _ => panic!("unexpected difference: {:?}", differences[0]),
+    }
+    assert_eq!(get_differences(a, "<div>a &lt; b</div>").len(), 0);
+
+    let a = "<svg><style><![CDATA[a < b]]></style></svg>";
+    let b = "<svg><style><![CDATA[a > c]]></style></svg>";
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    assert!(differences[0].is_node_text(), "{:?}", differences[0]);
+}
+
+#[test]
+fn test_element_content() {
+    let a = r#"<div><p class="a">text <b>bold</b></p></div>"#;
+    let b = r#"<div><ul><li>item</li></ul></div>"#;
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    match differences[0] {
+        Difference::NodeName { ref elem, ref opposite_elem } => {
+            assert_eq!(elem.element_content, r#"<p class="a">text <b>bold</b></p>"#);
+            assert_eq!(opposite_elem.element_content, "<ul><li>item</li></ul>");
+        }
+        _ => panic!("unexpected difference: {:?}", differences[0]),
+    }
+}
+
+#[test]
+fn test_diff_many() {
+    let pairs = vec![("<p>a</p>".to_owned(), "<p>a</p>".to_owned()),
+                     ("<p>a</p><b></b>".to_owned(), "<p>b</p><i></i>".to_owned())];
+    let results = diff_many(&pairs);
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_equal(), "{:?}", results[0]);
+    assert_eq!(results[1].index, 1);
+    assert!(!results[1].is_equal(), "{:?}", results[1]);
+    assert_eq!(results[1].count(DifferenceKind::NodeText), 1);
+    assert_eq!(results[1].count(DifferenceKind::NodeName), 1);
+    assert_eq!(results[1].count(DifferenceKind::NotPresent), 0);
+}
+
+#[test]
+fn test_decode_percent_encoding() {
+    let a = r#"<a href="/a%20b/%2F?q=%e2%82%ac" title="a%20b"></a>"#;
+    let b = r#"<a href="/a b/%2f?q=€" title="a b"></a>"#;
+    let options = DiffOptions::new().decode_percent_encoding(["href"]);
+    let differences = get_differences_with_options(a, b, &options);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    let options = DiffOptions::new().decode_percent_encoding(["href", "title"]);
+    assert_eq!(get_differences_with_options(a, b, &options).len(), 0);
+    // An invalid sequence falls back to the raw comparison.
+    let a = r#"<a href="%zz%"></a>"#;
+    assert_eq!(get_differences_with_options(a, a, &options).len(), 0);
+    assert_eq!(get_differences_with_options(a, r#"<a href="%ZZ%"></a>"#, &options).len(), 1);
+}
+
+#[test]
+fn test_depth() {
+    let a = "<div><p>a</p></div><b></b>";
+    let b = "<div><p>b</p></div><i></i>";
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 2, "{:?}", differences);
+    match differences[0] {
+        Difference::NodeText { ref elem, ref opposite_elem, .. } => {
+            assert_eq!(elem.depth, 4);
+            assert_eq!(opposite_elem.depth, 4);
+        }
+        _ => panic!("unexpected difference: {:?}", differences[0]),
+    }
+    match differences[1] {
+        Difference::NodeName { ref elem, .. } => assert_eq!(elem.depth, 2),
+        _ => panic!("unexpected difference: {:?}", differences[1]),
+    }
+}
+
+#[test]
+fn test_ignore_attribute_prefixes() {
+    let a = r#"<div data-v-1a2b class="a"><p _ngcontent-c0 ng-if="x">a</p></div>"#;
+    let b = r#"<div data-v-3c4d class="a"><p _ngcontent-c1>a</p></div>"#;
+    assert_eq!(get_differences(a, b).len(), 1);
+    let options = DiffOptions::new().ignore_attribute_prefixes(["data-v-", "_ngcontent-", "ng-"]);
+    let differences = get_differences_with_options(a, b, &options);
+    assert_eq!(differences.len(), 0, "{:?}", differences);
+
+    let b = r#"<div data-v-3c4d class="b"><p>a</p></div>"#;
+    let differences = get_differences_with_options(a, b, &options);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    match differences[0] {
+        Difference::NodeAttributes { ref elem_attributes, ref opposite_elem_attributes, .. } => {
+            assert_eq!(elem_attributes.keys().collect::<Vec<_>>(), vec!["class"]);
+            assert_eq!(opposite_elem_attributes.keys().collect::<Vec<_>>(), vec!["class"]);
+        }
+        _ => panic!("unexpected difference: {:?}", differences[0]),
+    }
+}
+
+#[test]
+fn test_render() {
+    let a = r#"<div class="b"><p>a</p></div>"#;
+    let b = r#"<div id="c"><p>a</p></div>"#;
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    let diff = &differences[0];
+    assert_eq!(diff.render(Verbosity::Terse), "/html[0]/body[0] => [Attributes differ]");
+    assert_eq!(diff.render(Verbosity::Normal), diff.to_string());
+    assert_eq!(diff.render(Verbosity::Verbose),
+               "[Attributes differ]\n\
+                \x20   path: /html[0]/body[0]\n\
+                \x20   expected: <div class=\"b\"><p>a</p></div>\n\
+                \x20   found: <div id=\"c\"><p>a</p></div>\n\
+                \x20   expected attributes: class=\"b\"\n\
+                \x20   found attributes: id=\"c\"");
+}
+
+#[test]
+fn test_custom_elements() {
+    let a = r#"<my-component foo="bar"><slot-item>a</slot-item></my-component>"#;
+    let b = r#"<my-component foo="baz"><slot-item>a</slot-item></my-component>"#;
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    match differences[0] {
+        Difference::NodeAttributes { ref elem, ref elem_attributes, ref opposite_elem_attributes,
+                                     .. } => {
+            assert_eq!(elem.element_name, "my-component");
+            assert_eq!(elem_attributes.get("foo").map(|s| s.as_str()), Some("bar"));
+            assert_eq!(opposite_elem_attributes.get("foo").map(|s| s.as_str()), Some("baz"));
+        }
+        _ => panic!("unexpected difference: {:?}", differences[0]),
+    }
+
+    let b = r#"<my-component foo="bar"><slot-item>b</slot-item></my-component>"#;
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    assert_eq!(differences[0].render(Verbosity::Terse),
+               "/html[0]/body[0]/my-component[0]/slot-item[0] => [Texts differ]");
+
+    let b = r#"<my-component foo="bar"><slot-item>a</slot-item><my-widget x-y="1"></my-widget>
+               </my-component>"#;
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    match differences[0] {
+        Difference::NotPresent { opposite_elem: Some(ref elem), .. } => {
+            assert_eq!(elem.element_name, "my-widget");
+            assert_eq!(elem.element_content, r#"<my-widget x-y="1"></my-widget>"#);
+        }
+        _ => panic!("unexpected difference: {:?}", differences[0]),
+    }
+
+    // Text nodes don't have a name.
+    let differences = get_differences("<div>hello world</div>", "<div><p></p></div>");
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    match differences[0] {
+        Difference::NodeType { ref elem, ref opposite_elem } => {
+            assert_eq!(elem.element_name, "");
+            assert_eq!(opposite_elem.element_name, "p");
+        }
+        _ => panic!("unexpected difference: {:?}", differences[0]),
+    }
+}
+
+#[test]
+fn test_case_insensitive_text() {
+    let a = "<p>Submit</p>";
+    let b = "<p>submit</p>";
+    assert_eq!(get_differences(a, b).len(), 1);
+    let options = DiffOptions::new().case_insensitive_text(true);
+    assert_eq!(get_differences_with_options(a, b, &options).len(), 0);
+
+    let b = "<p> SUBMIT\n</p>";
+    let differences = get_differences_with_options(a, b, &options);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    match differences[0] {
+        Difference::NodeText { ref elem_text, ref opposite_elem_text, .. } => {
+            assert_eq!(elem_text, "Submit");
+            assert_eq!(opposite_elem_text, " SUBMIT\n");
+        }
+        _ => panic!("unexpected difference: {:?}", differences[0]),
+    }
+    let options = options.normalize_whitespace(true);
+    assert_eq!(get_differences_with_options(a, b, &options).len(), 0);
+}
+
+#[test]
+fn test_max_depth() {
+    let a = "<div><p>a</p><ul><li>a</li><li>b</li></ul></div><b></b>";
+    let b = "<div><p>b</p><ul><li>c</li><li>d</li></ul></div><i></i>";
+    assert_eq!(get_differences(a, b).len(), 4);
+
+    let options = DiffOptions::new().max_depth(2);
+    let differences = get_differences_with_options(a, b, &options);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    assert!(differences[0].is_node_name(), "{:?}", differences[0]);
+
+    let options = options.mark_subtrees_below_max_depth(true);
+    let differences = get_differences_with_options(a, b, &options);
+    assert_eq!(differences.len(), 2, "{:?}", differences);
+    match differences[0] {
+        Difference::SubtreeDiffers { ref elem, count, .. } => {
+            assert_eq!(elem.element_name, "div");
+            assert_eq!(elem.path, "/html[0]/body[0]");
+            assert_eq!(count, 3);
+        }
+        _ => panic!("unexpected difference: {:?}", differences[0]),
+    }
+    assert!(differences[1].is_node_name(), "{:?}", differences[1]);
+
+    let options = options.max_differences(1);
+    let differences = get_differences_with_options(a, b, &options);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    assert!(differences[0].is_subtree_differs(), "{:?}", differences[0]);
+    let options = options.max_differences(0);
+    assert!(get_differences_with_options(a, b, &options).is_empty());
+}
+
+#[test]
+fn test_get_differences_against() {
+    let reference_content = "<div><p class=\"a\">text<!-- a -->more</p><b></b></div>";
+    let reference = parse_document(reference_content);
+    for candidate in &["<div><p class=\"a\">textmore</p><b></b></div>",
+                       "<div><p class=\"b\">text</p><i></i></div>",
+                       "<div><p class=\"a\">other</p></div>"] {
+        assert_eq!(get_differences_against(&reference, candidate),
+                   get_differences(reference_content, candidate));
+    }
+    // The reference tree isn't modified by the comparisons.
+    assert_eq!(reference.to_string(), parse_document(reference_content).to_string());
+    // The options are applied the same way.
+    let cases = [(DiffOptions::new().auto_unwrap(true), "<div><p class=\"b\">a</p></div>"),
+                 (DiffOptions::new().unordered(true), "<div><b></b><p class=\"a\">a</p></div>"),
+                 (DiffOptions::new().xml_syntax(true), "<item/><item>b</item>")];
+    for &(ref options, candidate) in &cases {
+        let reference_content = if options.xml_syntax {
+            "<item/><item>a</item>"
+        } else {
+            reference_content
+        };
+        let reference = parse_document_with_options(reference_content, options);
+        let differences = get_differences_against_with_options(&reference, candidate, options);
+        assert!(!differences.is_empty());
+        assert_eq!(differences,
+                   get_differences_with_options(reference_content, candidate, options));
+    }
+}
+
+#[test]
+fn test_reverse() {
+    let pairs = [("<div><foo></foo></div>", "<div><p></p></div>"),
+                 ("<div></div>", "<div class=\"b\"></div>"),
+                 ("<p>a</p>text", "<p>b</p><b></b>"),
+                 ("<ul><li>a</li></ul>", "<ul><li>a</li><li>b</li></ul>")];
+    for &(a, b) in &pairs {
+        let differences = get_differences(a, b);
+        assert!(!differences.is_empty(), "{:?} / {:?}", a, b);
+        assert_eq!(get_differences(b, a),
+                   differences.into_iter().map(Difference::reverse).collect::<Vec<_>>(),
+                   "{:?} / {:?}", a, b);
+    }
+}
+
+#[test]
+fn test_processing_instructions() {
+    let a = "<?xml-stylesheet href=\"a.css\"?><div>a</div><p></p>";
+    let b = "<div>a</div><p></p>";
+    let differences = get_differences(a, b);
+    assert_eq!(differences.len(), 0, "{:?}", differences);
+    let differences = get_differences(b, a);
+    assert_eq!(differences.len(), 0, "{:?}", differences);
+
+    let reference = parse_document(b);
+    let body = reference.select_first("body").expect("no body");
+    body.as_node().prepend(NodeRef::new_processing_instruction("xml-stylesheet", "href=\"a\""));
+    let differences = get_differences_against(&reference, b);
+    assert_eq!(differences.len(), 0, "{:?}", differences);
+}
+
+#[test]
+fn test_text_only_differences() {
+    let a = "<div><p class=\"a\">Hello <b>world</b></p></div>";
+    let b = "<section>Hello\n    world</section>";
+    assert_eq!(text_only_differences(a, b), None);
+
+    let b = "<section>Hello there</section>";
+    match text_only_differences(a, b) {
+        Some(Difference::NodeText { ref elem_text, ref opposite_elem_text, .. }) => {
+            assert_eq!(elem_text, "Hello world");
+            assert_eq!(opposite_elem_text, "Hello there");
+        }
+        x => panic!("unexpected result: {:?}", x),
+    }
+}
+
+#[test]
+fn test_build_difference_tree() {
+    let a = "<div><p>a</p><ul><li>a</li></ul></div><b></b>";
+    let b = "<div><p>b</p><ul><li>c</li></ul></div><i></i>";
+    let differences = get_differences(a, b);
+    let tree = build_difference_tree(&differences);
+    assert_eq!(tree.segment, "");
+    assert!(tree.differences.is_empty());
+    assert_eq!(tree.children.len(), 1);
+    let body = &tree.children[0].children[0];
+    assert_eq!(body.segment, "body[0]");
+    assert_eq!(body.differences.len(), 1);
+    assert!(body.differences[0].is_node_name(), "{:?}", body.differences[0]);
+    let div = &body.children[0];
+    assert_eq!(div.segment, "div[0]");
+    assert_eq!(div.children.iter().map(|c| c.segment.as_str()).collect::<Vec<_>>(),
+               vec!["p[0]", "ul[0]"]);
+    assert_eq!(div.children[1].children[0].segment, "li[0]");
+    assert_eq!(div.children[1].children[0].differences.len(), 1);
+    assert_eq!(tree.count(), 3);
+}
+
+#[test]
+fn test_unwrap_single_child_tags() {
+    let a = "<div class=\"wrapper\"><p>a</p></div><b></b>";
+    let b = "<p>a</p><b></b>";
+    assert_eq!(get_differences(a, b).len(), 1);
+    let options = DiffOptions::new().unwrap_single_child_tags(["div", "span"]);
+    let differences = get_differences_with_options(a, b, &options);
+    assert_eq!(differences.len(), 0, "{:?}", differences);
+    let differences = get_differences_with_options(b, a, &options);
+    assert_eq!(differences.len(), 0, "{:?}", differences);
+
+    // Changes inside the wrapped element are still reported.
+    let differences = get_differences_with_options(a, "<p>b</p><b></b>", &options);
+    assert_eq!(differences.len(), 1, "{:?}", differences);
+    assert!(differences[0].is_node_text(), "{:?}", differences[0]);
+
+    // Wrappers with more than one child or which aren't listed are kept.
+    let a = "<div><p>a</p><p>b</p></div>";
+    assert_eq!(get_differences_with_options(a, "<p>a</p>", &options).len(), 1);
+    let a = "<section><p>a</p></section>";
+    assert_eq!(get_differences_with_options(a, "<p>a</p>", &options).len(), 1);
+}
+
+#[test]
+fn test_similarity_score() {
+    let a = "<div><p>a</p><p>b</p><p>c</p></div>";
+    let options = DiffOptions::new();
+    assert_eq!(similarity_score(a, a, &options), 1.);
+    // 10 nodes (html, head, body, div, 3 p and their texts), 1 text difference.
+    let b = "<div><p>a</p><p>b</p><p>d</p></div>";
+    assert!((similarity_score(a, b, &options) - 9. / 10.).abs() < 1e-9);
+    // 1 text difference and a missing element.
+    let c = "<div><p>a</p><p>b</p><p>d</p><p>e</p></div>";
+    assert!((similarity_score(a, c, &options) - 10. / 12.).abs() < 1e-9);
+
+    let mut weights = HashMap::new();
+    weights.insert(DifferenceKind::NodeText, 0.5);
+    let options = DiffOptions::new().similarity_weights(weights);
+    assert!((similarity_score(a, b, &options) - (1. - 0.5 / 10.)).abs() < 1e-9);
+    assert!((similarity_score(a, c, &options) - (1. - 1.5 / 12.)).abs() < 1e-9);
+
+    let mut weights = HashMap::new();
+    weights.insert(DifferenceKind::NodeText, 0.);
+    let options = DiffOptions::new().similarity_weights(weights);
+    assert_eq!(similarity_score(a, b, &options), 1.);
+
+    // The comparison is the one of `get_differences_with_options`: 7 nodes without `html`,
+    // `head` and `body`.
+    let options = DiffOptions::new().auto_unwrap(true);
+    assert!((similarity_score(a, b, &options) - 6. / 7.).abs() < 1e-9);
+    let options = DiffOptions::new().strict_attribute_order(true);
+    assert!(similarity_score("<p a=\"1\" b=\"2\"></p>", "<p b=\"2\" a=\"1\"></p>", &options) < 1.);
+}
+
+#[test]
+fn test_whitespace_only_attribute() {
+    let differences = get_differences("<div class=\" a  b \"></div>",
+                                      "<div class=\"a b\"></div>");
+    assert_eq!(differences.len(), 1);
+    assert!(differences[0].is_whitespace_only_attribute());
+
+    let differences = get_differences("<div class=\"a b\"></div>", "<div class=\"a c\"></div>");
+    assert_eq!(differences.len(), 1);
+    assert!(!differences[0].is_whitespace_only_attribute());
+
+    let differences = get_differences("<div class=\"a\"></div>",
+                                      "<div class=\"a\" id=\"\"></div>");
+    assert_eq!(differences.len(), 1);
+    assert!(!differences[0].is_whitespace_only_attribute());
+}
+
+#[test]
+fn test_warnings() {
+    let document = "<!DOCTYPE html><html><body><ul><li>a</li></ul></body></html>";
+    assert_eq!(get_warnings("<li>a</li>", document),
+               vec![DiffWarning::FragmentComparedToDocument { first_is_fragment: true }]);
+    assert_eq!(get_warnings(document, "<li>a</li>"),
+               vec![DiffWarning::FragmentComparedToDocument { first_is_fragment: false }]);
+    assert_eq!(get_warnings("<!-- header --><HTML><body></body></HTML>", "<li>a</li>"),
+               vec![DiffWarning::FragmentComparedToDocument { first_is_fragment: false }]);
+    assert!(get_warnings(document, document).is_empty());
+    assert!(get_warnings("<li>a</li>", "<li>b</li>").is_empty());
+    assert!(get_warnings(document, "").is_empty());
+
+    let results = diff_many(&[("<li>a</li>".to_owned(), document.to_owned())]);
+    assert_eq!(results[0].warnings.len(), 1);
+}
+
+#[test]
+fn test_form_semantics() {
+    let options = DiffOptions::new().form_semantics(true);
+    let select1 = "<select><option>a</option><option>b</option></select>";
+    let select2 = "<select><option selected>a</option><option>b</option></select>";
+    assert_eq!(get_differences(select1, select2).len(), 1);
+    assert!(get_differences_with_options(select1, select2, &options).is_empty());
+    // The last selected option wins.
+    let select3 = "<select><option selected>a</option><option selected>b</option></select>";
+    let select4 = "<select><option>a</option><option selected>b</option></select>";
+    assert!(get_differences_with_options(select3, select4, &options).is_empty());
+    assert_eq!(get_differences_with_options(select1, select4, &options).len(), 2);
+    // Disabled options aren't selected by default.
+    let select5 = "<select><option disabled>a</option><option>b</option></select>";
+    let select6 = "<select><option disabled>a</option><option selected>b</option></select>";
+    assert!(get_differences_with_options(select5, select6, &options).is_empty());
+    // No default selection with `multiple`.
+    let select7 = "<select multiple><option>a</option></select>";
+    let select8 = "<select multiple><option selected>a</option></select>";
+    assert_eq!(get_differences_with_options(select7, select8, &options).len(), 1);
+
+    assert!(get_differences_with_options("<input checked=\"checked\">",
+                                         "<input checked type=\"TEXT\">",
+                                         &options).is_empty());
+    assert_eq!(get_differences_with_options("<input checked>", "<input>", &options).len(), 1);
+    assert!(get_differences_with_options("<option value=\"a\"> a </option>",
+                                         "<option> a </option>",
+                                         &options).is_empty());
+    // The attributes it sets are only compared if the other options allow it.
+    let only_class = options.clone().only_compare_attributes(vec!["class"]);
+    assert!(get_differences_with_options(select1, select4, &only_class).is_empty());
+    let no_selected = options.ignore_attribute_prefixes(vec!["selected"]);
+    assert!(get_differences_with_options(select1, select4, &no_selected).is_empty());
+}
+
+#[test]
+fn test_path_glob_matches() {
+    let differences = get_differences("<main><div><p>a</p></div><div><p>b</p></div></main>",
+                                      "<main><div><p>a</p></div><div><p>c</p></div></main>");
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].elem_information().path, "/html[0]/body[0]/main[0]/div[1]/p[0]");
+    let diff = &differences[0];
+    assert!(path_glob_matches(diff, "/html/body/main/**"));
+    assert!(path_glob_matches(diff, "/**"));
+    assert!(path_glob_matches(diff, "/**/p"));
+    assert!(path_glob_matches(diff, "/html/body/*/div[1]/*"));
+    assert!(path_glob_matches(diff, "/html/**/div/p/**"));
+    assert!(!path_glob_matches(diff, "/html/body/*/p"));
+    assert!(!path_glob_matches(diff, "/html/body/main/div[0]/**"));
+    assert!(!path_glob_matches(diff, "/html/body/main"));
+}
+
+#[test]
+fn test_annotated_diff() {
+    let tree = annotated_diff("<div><p>a</p><p class=\"x\">b</p><ul></ul></div>",
+                              "<div><p>a</p><p class=\"y\">b</p></div><span></span>");
+    let body = &tree.children[0].children[1];
+    assert_eq!(body.element_name, "body");
+    assert_eq!(body.annotation, Annotation::Matched);
+    assert_eq!(body.children.len(), 2);
+    assert_eq!(body.children[1].element_name, "span");
+    assert_eq!(body.children[1].annotation, Annotation::OnlyRight);
+
+    let div = &body.children[0];
+    assert_eq!(div.annotation, Annotation::Matched);
+    assert_eq!(div.path, "/html[0]/body[0]/div[0]");
+    assert_eq!(div.children.len(), 3);
+    assert_eq!(div.children[0].annotation, Annotation::Matched);
+    assert_eq!(div.children[0].children[0].annotation, Annotation::Matched);
+    match div.children[1].annotation {
+        Annotation::Changed(ref diff) => assert!(diff.is_node_attributes()),
+        ref a => panic!("unexpected annotation: {:?}", a),
+    }
+    assert!(div.children[1].children.is_empty());
+    assert_eq!(div.children[2].element_name, "ul");
+    assert_eq!(div.children[2].annotation, Annotation::OnlyLeft);
+
+    // A differing element takes its position.
+    let tree = annotated_diff("<p>a</p><div></div><p><b>x</b></p>",
+                              "<span>a</span><div></div><p><b>y</b></p>");
+    let body = &tree.children[0].children[1];
+    assert!(body.children[0].annotation != Annotation::Matched);
+    let b = &body.children[2].children[0];
+    assert_eq!(b.path, "/html[0]/body[0]/p[1]/b[0]");
+    assert_eq!(b.annotation, Annotation::Matched);
+    match b.children[0].annotation {
+        Annotation::Changed(ref diff) => assert!(diff.is_node_text()),
+        ref a => panic!("unexpected annotation: {:?}", a),
+    }
+
+    // The options changing how the nodes are matched are used.
+    let options = DiffOptions::new().unordered(true).auto_unwrap(true);
+    let tree = annotated_diff_with_options("<p>a</p><ul><li>b</li><li>c</li></ul>",
+                                           "<ul><li>c</li><li>d</li></ul><p>a</p>", &options);
+    assert_eq!(tree.element_name, "body");
+    assert_eq!(tree.children[0].annotation, Annotation::Matched);
+    assert_eq!(tree.children[0].path, "/p[0]");
+    assert_eq!(tree.children[0].children.len(), 1);
+    let ul = &tree.children[1];
+    assert_eq!(ul.path, "/ul[0]");
+    let annotations = |tree: &AnnotatedTree| {
+        tree.children.iter().map(|c| (c.path.clone(), c.annotation.clone())).collect::<Vec<_>>()
+    };
+    assert_eq!(annotations(ul), [("/ul[0]/li[1]".to_owned(), Annotation::Matched),
+                                 ("/ul[0]/li[0]".to_owned(), Annotation::Matched)]);
+    // The `<li>`s with different texts are compared.
+    assert_eq!(annotations(&ul.children[1]), [("/ul[0]/li[0]".to_owned(), Annotation::OnlyLeft),
+                                              ("/ul[0]/li[0]".to_owned(), Annotation::OnlyRight)]);
+}
+
+#[test]
+fn test_accessibility() {
+    let options = DiffOptions::accessibility();
+    let differences = get_differences_with_options(
+        "<button class=\"a\" aria-label=\"Close\">x</button>",
+        "<button class=\"b\" aria-label=\"Open\">y</button>",
+        &options);
+    assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::NodeAttributes { ref elem_attributes, ref opposite_elem_attributes, .. } => {
+            assert_eq!(elem_attributes.len(), 1);
+            assert_eq!(elem_attributes["aria-label"], "Close");
+            assert_eq!(opposite_elem_attributes["aria-label"], "Open");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    assert!(get_differences_with_options("<div role=\"tab\" id=\"a\">a</div>",
+                                         "<div role=\"tab\">b</div>",
+                                         &options).is_empty());
+    assert_eq!(get_differences_with_options("<div role=\"tab\"></div>", "<div></div>",
+                                            &options).len(), 1);
+    // Structural differences are still reported.
+    assert_eq!(get_differences_with_options("<div></div>", "<span></span>", &options).len(), 1);
+}
+
+#[test]
+fn test_resolve_svg_use() {
+    let svg = |color: &str, id: &str| {
+        format!("<svg><defs><symbol id=\"icon\"><circle fill=\"{}\"/></symbol></defs>\
+                 <use href=\"#{}\"/></svg>", color, id)
+    };
+    let options = DiffOptions::new().resolve_svg_use(true);
+    // Without the option, only the `<symbol>` itself differs.
+    assert_eq!(get_differences(&svg("red", "icon"), &svg("blue", "icon")).len(), 1);
+    let differences = get_differences_with_options(&svg("red", "icon"), &svg("blue", "icon"),
+                                                   &options);
+    assert_eq!(differences.len(), 2);
+    assert_eq!(differences[1].elem_information().path,
+               "/html[0]/body[0]/svg[0]/use[0]/symbol[0]");
+    assert!(get_differences_with_options(&svg("red", "icon"), &svg("red", "icon"),
+                                         &options).is_empty());
+    // Dangling references.
+    assert!(get_differences_with_options(&svg("red", "none"), &svg("red", "none"),
+                                         &options).is_empty());
+    // A reference to an ancestor doesn't loop.
+    let cycle = "<svg><g id=\"g\"><use href=\"#g\"/></g></svg>";
+    assert!(get_differences_with_options(cycle, cycle, &options).is_empty());
+}
+
+#[test]
+fn test_content_eq() {
+    let diff1 = get_differences("<p>a</p>", "<p>b</p>").pop().unwrap();
+    let diff2 = get_differences("<div><p>a</p></div>", "<div><p>b</p></div>").pop().unwrap();
+    let diff3 = get_differences("<div><p>a</p></div>", "<div><p>c</p></div>").pop().unwrap();
+    assert!(diff1 != diff2);
+    assert!(diff1.content_eq(&diff2));
+    assert!(!diff1.content_eq(&diff3));
+    assert!(!diff1.content_eq(&diff1.clone().reverse()));
+
+    let missing1 = get_differences("<p>a</p>", "").pop().unwrap();
+    let missing2 = get_differences("<div><p>a</p></div>", "<div></div>").pop().unwrap();
+    assert!(missing1.content_eq(&missing2));
+}
+
+#[test]
+fn test_min_text_len() {
+    let options = DiffOptions::new().min_text_len(2);
+    assert!(get_differences_with_options("<p>.</p>", "<p>!</p>", &options).is_empty());
+    assert_eq!(get_differences_with_options("<p>.</p>", "<p>ab</p>", &options).len(), 1);
+    assert_eq!(get_differences("<p>.</p>", "<p>!</p>").len(), 1);
+    // The length is counted after the normalization.
+    let options = options.normalize_whitespace(true);
+    assert!(get_differences_with_options("<p> . </p>", "<p>é</p>", &options).is_empty());
+}
+
+#[test]
+fn test_unordered() {
+    let options = DiffOptions::new().unordered(true);
+    assert!(get_differences_with_options("<ul><li>a</li><li>b</li></ul><p>c</p>",
+                                         "<p>c</p><ul><li>b</li><li>a</li></ul>",
+                                         &options).is_empty());
+    // Only the differing parts of the subtrees are reported.
+    let differences = get_differences_with_options("<ul><li>a</li><li>b</li></ul>",
+                                                   "<ul><li>b</li><li>c</li></ul>",
+                                                   &options);
+    assert_eq!(differences.len(), 2);
+    assert_eq!(differences[0].elem_information().path, "/html[0]/body[0]/ul[0]/li[0]");
+    // Multisets: the number of occurrences matters.
+    let differences = get_differences_with_options("<p>a</p><p>a</p><p>b</p>",
+                                                   "<p>b</p><p>a</p><p>c</p>",
+                                                   &options);
+    assert_eq!(differences.len(), 2);
+    assert!(differences.iter().all(|d| d.is_not_present()));
+    match differences[0] {
+        Difference::NotPresent { elem: Some(ref elem), opposite_elem: None } => {
+            assert_eq!(elem.element_content, "a");
+            assert_eq!(elem.path, "/html[0]/body[0]/p[1]");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    match differences[1] {
+        Difference::NotPresent { elem: None, opposite_elem: Some(ref elem) } => {
+            assert_eq!(elem.element_content, "c");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+}
+
+#[test]
+fn test_diff_error() {
+    use std::error::Error;
+
+    let options = DiffOptions::new();
+    let read = |content: &[u8]| get_differences_from_readers(content, &b"<p>a</p>"[..], &options);
+    assert!(read(b"<p>a</p>").unwrap().is_empty());
+    let err = read(&[b'a', 0xff]).unwrap_err();
+    assert_eq!(err.to_string(), "not valid UTF-8 (invalid byte at offset 1)");
+    assert!(err.source().is_some());
+    let err = read(b"").unwrap_err();
+    match err {
+        DiffError::EmptyInput => {}
+        ref e => panic!("unexpected error: {:?}", e),
+    }
+    assert_eq!(err.to_string(), "empty input");
+    assert!(err.source().is_none());
+    let err: DiffError = ::std::io::Error::new(::std::io::ErrorKind::NotFound, "no file").into();
+    assert_eq!(err.to_string(), "no file");
+}
+
+#[test]
+fn test_skeleton() {
+    let options = DiffOptions::skeleton(["class"]);
+    assert!(get_differences_with_options(
+        "<div class=\"card\" style=\"color: red\"><h2>Title</h2><p>Some text</p></div>",
+        "<div class=\"card\" data-id=\"2\"><h2>Other title</h2><p>Other text</p></div>",
+        &options).is_empty());
+    assert_eq!(get_differences_with_options("<div class=\"a\"></div>",
+                                            "<div class=\"b\"></div>",
+                                            &options).len(), 1);
+    assert_eq!(get_differences_with_options("<div><h2>a</h2></div>", "<div><h3>a</h3></div>",
+                                            &options).len(), 1);
+    assert_eq!(get_differences_with_options("<div>a</div>", "<div><b>a</b></div>",
+                                            &options).len(), 1);
+}
+
+#[test]
+fn test_normalize_tables() {
+    // The parser already adds the implicit `<tbody>`.
+    assert!(get_differences("<table><tr><td>a</td></tr></table>",
+                            "<table><tbody><tr><td>a</td></tr></tbody></table>").is_empty());
+
+    let options = DiffOptions::new().normalize_tables(true);
+    let table1 = "<table><tr><th>h</th></tr><tr><td>a</td></tr></table>";
+    let table2 = "<table><thead><tr><th>h</th></tr></thead><tbody><tr><td>a</td></tr></tbody>\
+                  </table>";
+    assert_eq!(get_differences(table1, table2).len(), 2);
+    assert!(get_differences_with_options(table1, table2, &options).is_empty());
+
+    // Mismatched column counts.
+    let differences = get_differences_with_options(
+        "<table><tr><td>a</td><td>b</td></tr></table>",
+        "<table><thead><tr><td>a</td></tr></thead></table>",
+        &options);
+    assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::NotPresent { elem: Some(ref elem), opposite_elem: None } => {
+            assert_eq!(elem.element_content, "<td>b</td>");
+            assert_eq!(elem.path, "/html[0]/body[0]/table[0]/tr[0]");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+}
+
+#[test]
+fn test_on_match() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let matched = Rc::new(RefCell::new(Vec::new()));
+    let matched2 = matched.clone();
+    let options = DiffOptions::new().on_match(move |node1, node2, path| {
+        assert_eq!(node1.as_element().map(|e| e.name.clone()),
+                   node2.as_element().map(|e| e.name.clone()));
+        matched2.borrow_mut().push(format!("{} {}", path, node1.to_string()));
+    });
+    let differences = get_differences_with_options("<p>a</p><ul><li>b</li></ul>",
+                                                   "<p>a</p><ul><li>c</li></ul>",
+                                                   &options);
+    assert_eq!(differences.len(), 1);
+    let matched = matched.borrow();
+    assert!(matched.contains(&"/html[0]/body[0] <p>a</p>".to_owned()));
+    assert!(matched.contains(&"/html[0]/body[0]/p[0] a".to_owned()));
+    assert!(matched.contains(&"/html[0]/body[0]/ul[0] <li>b</li>".to_owned()));
+    // The texts of the `li` elements differ.
+    assert!(!matched.iter().any(|m| m.contains("/li[0]")));
+}
+
+#[test]
+fn test_resolve_urls_against() {
+    let options = DiffOptions::new().resolve_urls_against("https://a.com/docs/index.html",
+                                                          "https://a.com/docs/guide/");
+    let check = |href1: &str, href2: &str| {
+        get_differences_with_options(&format!("<a href=\"{}\"></a>", href1),
+                                     &format!("<a href=\"{}\"></a>", href2),
+                                     &options).is_empty()
+    };
+    assert!(check("page.html", "../page.html"));
+    assert!(check("../img/a.png?x=1#top", "https://a.com/img/a.png?x=1#top"));
+    assert!(check("./", "//a.com/docs/guide/../"));
+    assert!(check("#section", "../index.html#section"));
+    assert!(!check("#section", "index.html#section"));
+    assert!(!check("page.html", "other.html"));
+    // Not URL attributes.
+    assert_eq!(get_differences_with_options("<a title=\"/v1/docs/a\"></a>",
+                                            "<a title=\"a\"></a>",
+                                            &options).len(), 1);
+    // Invalid bases are ignored.
+    let options = DiffOptions::new().resolve_urls_against("not a url", "/v2/");
+    assert_eq!(get_differences_with_options("<a href=\"/v2/a\"></a>", "<a href=\"a\"></a>",
+                                            &options).len(), 1);
+}
+
+#[test]
+fn test_parent_content() {
+    let differences = get_differences("<ul class=\"list\" id=\"a&quot;\"><li>a</li></ul>",
+                                      "<ul class=\"list\" id=\"a&quot;\"></ul>");
+    assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::NotPresent { elem: Some(ref elem), opposite_elem: None } => {
+            assert_eq!(elem.parent_content,
+                       Some("<ul class=\"list\" id=\"a&quot;\">".to_owned()));
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    // At the document level.
+    let differences = get_differences_against(&parse_document("<p>a</p>"), "<p>b</p>");
+    assert_eq!(differences[0].elem_information().parent_content, Some("<p>".to_owned()));
+
+    let options = DiffOptions::new().max_content_len(6);
+    let differences = get_differences_with_options("<div class=\"abcdef\"><p>a</p></div>",
+                                                   "<div class=\"abcdef\"><b>a</b></div>",
+                                                   &options);
+    match differences[0] {
+        Difference::NodeName { ref elem, ref opposite_elem } => {
+            assert_eq!(elem.element_content, "<p>a</...");
+            assert_eq!(opposite_elem.element_content, "<b>a</...");
+            assert_eq!(elem.parent_content, Some("<div c...".to_owned()));
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+}
+
+#[test]
+fn test_strip_attribute_affixes() {
+    let options = DiffOptions::new().strip_attribute_affixes(["src", "href"],
+                                                             ["https://cdn1", "https://cdn2"],
+                                                             [".min.js", "?"]);
+    let check = |src1: &str, src2: &str| {
+        get_differences_with_options(&format!("<script src=\"{}\"></script>", src1),
+                                     &format!("<script src=\"{}\"></script>", src2),
+                                     &options)
+    };
+    assert!(check("https://cdn1/app.js?v=123", "https://cdn2/app.js?v=456").is_empty());
+    assert!(check("/app.js", "/app.js#x").is_empty());
+    assert!(check("app.min.js", "app").is_empty());
+    let differences = check("https://cdn1/app.js?v=1", "https://cdn2/lib.js?v=1");
+    assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::NodeAttributes { ref elem_attributes, .. } => {
+            assert_eq!(elem_attributes["src"], "https://cdn1/app.js?v=1");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    // Only the listed attributes are stripped.
+    assert_eq!(get_differences_with_options("<img alt=\"a?1\">", "<img alt=\"a?2\">",
+                                            &options).len(), 1);
+}
+
+#[test]
+fn test_render_github_annotations() {
+    let options = DiffOptions::new();
+    assert_eq!(render_github_annotations("<p>a</p>\n<ul></ul>", "<div>\n<p>100%\nb</p></div>",
+                                         &options, "out/a,b.html"),
+               "::error file=out/a%2Cb.html,line=1,col=1,title=Tags differ::/html[0]/body[0] => \
+                [Tags differ]: expected \"p\", found \"div\"\n\
+                ::error file=out/a%2Cb.html,line=1,col=1,title=One element is missing::\
+                /html[0]/body[0] => [One element is missing]: element <ul> present in left but \
+                missing in right\n");
+    assert_eq!(render_github_annotations("<div><p>a</p></div>", "<div>\n<p>100%\nb</p></div>",
+                                         &options, "a.html"),
+               "::error file=a.html,line=2,col=1,title=Texts differ::/html[0]/body[0]/div[0]/p[0] \
+                => [Texts differ]: expected \"a\", found \"100%25\\nb\"\n");
+    assert_eq!(render_github_annotations("<p>a</p>", "<p>a</p>", &options, "a.html"), "");
+}
+
+#[test]
+fn test_head_meta_link_unordered() {
+    let head = |content: &str| {
+        format!("<html><head>{}</head><body></body></html>", content)
+    };
+    let head1 = head("<meta charset=\"utf-8\"><title>a</title>\
+                      <meta name=\"description\" content=\"d\">\
+                      <link rel=\"stylesheet\" href=\"a.css\"><link rel=\"icon\" href=\"i.png\">");
+    let head2 = head("<link rel=\"icon\" href=\"i.png\"><title>a</title>\
+                      <meta name=\"description\" content=\"d\">\
+                      <link rel=\"stylesheet\" href=\"a.css\"><meta charset=\"utf-8\">");
+    let options = DiffOptions::new().head_meta_link_unordered(true);
+    assert!(!get_differences(&head1, &head2).is_empty());
+    assert!(get_differences_with_options(&head1, &head2, &options).is_empty());
+
+    // A changed meta tag.
+    let differences = get_differences_with_options(
+        &head("<meta name=\"description\" content=\"a\"><meta name=\"author\" content=\"b\">"),
+        &head("<meta name=\"author\" content=\"b\"><meta name=\"description\" content=\"c\">"),
+        &options);
+    assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::NodeAttributes { ref elem_attributes, ref opposite_elem_attributes, .. } => {
+            assert_eq!(elem_attributes["content"], "a");
+            assert_eq!(opposite_elem_attributes["content"], "c");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    // Added and removed tags.
+    let differences = get_differences_with_options(
+        &head("<link rel=\"stylesheet\" href=\"a.css\">"),
+        &head("<link rel=\"stylesheet\" href=\"b.css\">"),
+        &options);
+    assert_eq!(differences.len(), 2);
+    assert!(differences.iter().all(|d| d.is_not_present()));
+    // The rest of `<head>` is still compared in order.
+    assert_eq!(get_differences_with_options(&head("<title>a</title><style></style>"),
+                                            &head("<style></style><title>a</title>"),
+                                            &options).len(), 2);
+}
+
+#[test]
+fn test_element_name() {
+    let differences = get_differences("<div><p>a</p><ul></ul></div>", "<div><b>a</b></div><img>");
+    assert_eq!(differences.len(), 3);
+    assert_eq!(differences[0].element_name(), "p");
+    assert_eq!(differences[0].path(), "/html[0]/body[0]/div[0]");
+    assert_eq!(differences[1].element_name(), "ul");
+    // Only in the second content.
+    assert_eq!(differences[2].element_name(), "img");
+    assert_eq!(differences[2].path(), "/html[0]/body[0]");
+    assert_eq!(get_differences("<p>a</p>", "<p>b</p>")[0].element_name(), "");
+}
+
+#[test]
+fn test_element_content_quotes() {
+    let differences = get_differences("<p title='a \"b\" &amp; &#39;c&#39;'>x</p>", "<b>x</b>");
+    let content = match differences[0] {
+        Difference::NodeName { ref elem, .. } => elem.element_content.clone(),
+        ref d => panic!("unexpected difference: {:?}", d),
+    };
+    assert_eq!(content, "<p title=\"a &quot;b&quot; &amp; 'c'\">x</p>");
+    // The content can be parsed again.
+    let p = parse_document(&content).select_first("p").unwrap();
+    assert_eq!(p.attributes.borrow().get("title"), Some("a \"b\" & 'c'"));
+    assert!(get_differences(&content, "<p title='a \"b\" &amp; &#39;c&#39;'>x</p>").is_empty());
+}
+
+#[test]
+fn test_shallow_differences() {
+    let a = "<main><ul><li>a</li><li>b</li></ul></main><nav><a>c</a></nav><footer></footer>";
+    let b = "<main><ul><li>d</li><li>e</li></ul></main><nav><a>c</a></nav><aside></aside>";
+    let differences = shallow_differences(a, b, 2);
+    assert_eq!(differences.len(), 2, "{:?}", differences);
+    match differences[0] {
+        Difference::SubtreeDiffers { ref elem, count, .. } => {
+            assert_eq!(elem.element_name, "main");
+            assert_eq!(count, 2);
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    assert!(differences[1].is_node_name());
+    assert_eq!(shallow_differences(a, b, 10), get_differences(a, b));
+}
+
+#[test]
+fn test_auto_unwrap() {
+    let options = DiffOptions::new().auto_unwrap(true);
+    let differences = get_differences_with_options("<ul><li>a</li></ul>", "<ul><li>b</li></ul>",
+                                                   &options);
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].path(), "/ul[0]/li[0]");
+    assert_eq!(differences[0].elem_information().depth, 2);
+    let differences = get_differences_with_options("<p>a</p>", "<header>a</header>", &options);
+    assert_eq!(differences[0].path(), "");
+    assert_eq!(differences[0].elem_information().parent_content, Some("<body>".to_owned()));
+
+    // Not applied to documents, or if the fragment has content in `<head>`.
+    let differences = get_differences_with_options("<body><p>a</p></body>", "<p>b</p>",
+                                                   &options);
+    assert_eq!(differences[0].path(), "/html[0]/body[0]/p[0]");
+    let differences = get_differences_with_options("<title>a</title><p>a</p>",
+                                                   "<title>a</title><p>b</p>",
+                                                   &options);
+    assert_eq!(differences[0].path(), "/html[0]/body[0]/p[0]");
+    assert_eq!(get_differences("<p>a</p>", "<p>b</p>")[0].path(), "/html[0]/body[0]/p[0]");
+}
+
+#[test]
+fn test_charset_difference() {
+    let content_type = |charset: &str| {
+        format!("<meta http-equiv=\"Content-Type\" content=\"text/html; charset={}\">", charset)
+    };
+    assert_eq!(charset_difference("<meta charset=\"utf-8\">", &content_type("UTF-8")), None);
+    assert_eq!(charset_difference(&content_type("'iso-8859-1'"), "<meta charset=\"utf-8\">"),
+               Some((Some("iso-8859-1".to_owned()), Some("utf-8".to_owned()))));
+    assert_eq!(charset_difference("<p>a</p>", "<meta charset=\"utf-8\">"),
+               Some((None, Some("utf-8".to_owned()))));
+    assert_eq!(charset_difference("<p>a</p>", "<meta name=\"author\" content=\"a\">"), None);
+}
+
+#[test]
+fn test_title_difference() {
+    assert_eq!(title_difference("<title>Home  page</title>", "<title>\n Home page </title>"),
+               None);
+    assert_eq!(title_difference("<p>a</p>", "<p>b</p>"), None);
+    assert_eq!(title_difference("<title>Home</title>", "<title>About</title>"),
+               Some(Difference::NodeText {
+                   elem: ElementInformation::new("", "", "/html[0]/head[0]/title[0]"),
+                   elem_text: "Home".to_owned(),
+                   opposite_elem: ElementInformation::new("", "", "/html[0]/head[0]/title[0]"),
+                   opposite_elem_text: "About".to_owned(),
+               }));
+    assert_eq!(title_difference("<p>a</p>", "<title>Home</title>"),
+               Some(Difference::NotPresent {
+                   elem: None,
+                   opposite_elem: Some(ElementInformation::new("title", "<title>Home</title>",
+                                                               "/html[0]/head[0]")),
+               }));
+}
+
+#[test]
+fn test_allow_attribute_additions() {
+    let options = DiffOptions::default().allow_attribute_additions(true);
+    assert!(get_differences_with_options("<div><p>a</p></div>",
+                                         "<div id=\"x\"><p data-a=\"1\" data-b=\"2\">a</p></div>",
+                                         &options).is_empty());
+    // Changing an existing attribute is still reported.
+    assert_eq!(get_differences_with_options("<p class=\"a\">a</p>",
+                                            "<p class=\"b\" data-a=\"1\">a</p>",
+                                            &options).len(), 1);
+    // So is removing one.
+    assert_eq!(get_differences_with_options("<p class=\"a\" id=\"x\">a</p>",
+                                            "<p class=\"a\" data-a=\"1\">a</p>",
+                                            &options).len(), 1);
+    assert_eq!(get_differences("<p>a</p>", "<p data-a=\"1\">a</p>").len(), 1);
+}
+
+#[test]
+fn test_equivalent_tags() {
+    let options = DiffOptions::default().equivalent_tags([("b", "strong")]);
+    assert!(get_differences_with_options("<p><b>a</b><strong>b</strong></p>",
+                                         "<p><strong>a</strong><b>b</b></p>",
+                                         &options).is_empty());
+    let differences = get_differences_with_options("<p><b>a</b></p>",
+                                                   "<p><strong class=\"x\">a</strong></p>",
+                                                   &options);
+    assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::NodeAttributes { ref elem, ref opposite_elem, .. } => {
+            assert_eq!(elem.element_name, "b");
+            assert_eq!(opposite_elem.element_name, "strong");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    assert_eq!(get_differences_with_options("<p><b>a</b></p>", "<p><em>a</em></p>",
+                                            &options).len(), 1);
+}
+
+#[test]
+fn test_get_differences_filtered() {
+    let is_img = |node: &NodeRef| is_element_named(node, &["img"]);
+    assert!(get_differences_filtered("<div><img src=\"a.png\"><p>a</p></div>",
+                                     "<img src=\"a.png\"><span>b</span>",
+                                     is_img).is_empty());
+    let differences = get_differences_filtered("<img src=\"a.png\"><p><img src=\"b.png\"></p>",
+                                               "<img src=\"a.png\"><img src=\"c.png\">",
+                                               is_img);
+    assert_eq!(differences.len(), 1);
+    assert!(differences[0].is_node_attributes());
+    assert_eq!(differences[0].path(), "/html[0]/body[0]/p[0]");
+    match differences[0] {
+        Difference::NodeAttributes { ref elem, ref opposite_elem, .. } => {
+            assert_eq!(elem.parent_content, Some("<p>".to_owned()));
+            assert_eq!(opposite_elem.parent_content, Some("<body>".to_owned()));
+        }
+        _ => unreachable!(),
+    }
+    let differences = get_differences_filtered("<img src=\"a.png\">",
+                                               "<img src=\"a.png\"><img src=\"b.png\">",
+                                               is_img);
+    assert_eq!(differences,
+               vec![Difference::NotPresent {
+                   elem: None,
+                   opposite_elem: Some(ElementInformation {
+                       element_name: "img".to_owned(),
+                       element_content: "<img src=\"b.png\">".to_owned(),
+                       path: "/html[0]/body[0]".to_owned(),
+                       depth: 2,
+                       parent_content: Some("<body>".to_owned()),
+                   }),
+               }]);
+    // The paths below the matching elements keep their position.
+    let is_figure = |node: &NodeRef| is_element_named(node, &["figure"]);
+    let content1 = "<figure><p>a</p></figure><figure><p>b</p></figure>";
+    let content2 = "<figure><p>a</p></figure><figure><p>c</p></figure>";
+    let differences = get_differences_filtered(content1, content2, is_figure);
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].path(), "/html[0]/body[0]/figure[1]/p[0]");
+    assert_eq!(differences, get_differences(content1, content2));
+}
+
+#[test]
+fn test_handle_other_node() {
+    assert!(get_differences("<!DOCTYPE html><p>a</p>", "<!doctype HTML><p>a</p>").is_empty());
+    let html4 = "<!DOCTYPE HTML PUBLIC \"-//W3C//DTD HTML 4.01//EN\" \
+                 \"http://www.w3.org/TR/html4/strict.dtd\"><p>a</p>";
+    let differences = get_differences("<!DOCTYPE html><p>a</p>", html4);
+    assert_eq!(differences.len(), 1);
+    assert!(differences[0].is_node_type());
+    assert_eq!(differences[0].path(), "");
+    // The content of the templates is compared too.
+    assert!(get_differences("<template><p>a</p></template>",
+                            "<template><p>a</p></template>").is_empty());
+    let differences = get_differences("<template><p>a</p></template>",
+                                      "<template><p>b</p></template>");
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].path(), "/html[0]/head[0]/template[0]/p[0]");
+}
+
+#[test]
+fn test_apply_differences() {
+    let document = parse_document("<p class=\"x\" title=\"t\">a</p><p>b</p><p>c</p>");
+    let target = "<p class=\"y\" lang=\"en\">a</p><p>b</p><p>d</p>";
+    let differences = get_differences_against(&document, target);
+    assert_eq!(differences.len(), 2);
+    // The first `<p>` differs but still counts in the path of the last one.
+    assert_eq!(differences[1].path(), "/html[0]/body[0]/p[2]");
+    let patched = apply_differences(&document, &differences).unwrap();
+    assert!(get_differences_against(&patched, target).is_empty());
+    // The original document isn't modified.
+    assert_eq!(get_differences_against(&document, target).len(), 2);
+
+    let differences = get_differences_against(&document, "<p class=\"x\" title=\"t\">a</p>");
+    assert_eq!(apply_differences(&document, &differences),
+               Err(ApplyError::Unsupported(DifferenceKind::NotPresent)));
+    let differences = get_differences("<p>a</p>", "<p>b</p>");
+    assert_eq!(apply_differences(&parse_document("<p>c</p>"), &differences),
+               Err(ApplyError::NodeNotFound("/html[0]/body[0]/p[0]".to_owned())));
+
+    // The texts split by a comment are compared as one.
+    let document = parse_document("<p>a<!-- c -->b<i>c</i>d</p>");
+    let differences = get_differences_against(&document, "<p>xy<i>c</i>d</p>");
+    let patched = apply_differences(&document, &differences).unwrap();
+    assert_eq!(patched.select_first("p").unwrap().text_contents(), "xycd");
+    // Unless `collapse_text_nodes` is disabled.
+    let options = DiffOptions::new().collapse_text_nodes(false);
+    let differences = get_differences_against_with_options(&document,
+                                                           "<p>a<!-- c -->z<i>c</i>d</p>",
+                                                           &options);
+    let patched = apply_differences(&document, &differences).unwrap();
+    assert_eq!(patched.select_first("p").unwrap().text_contents(), "azcd");
+}
+
+#[test]
+fn test_normalize_dom() {
+    let options = DiffOptions::default().normalize_dom(true);
+    let document = parse_document("<p>a</p><svg></svg>");
+    for (selector, name) in &[("p", "dataFoo"), ("svg", "viewbox")] {
+        let elem = document.select_first(selector).unwrap();
+        elem.attributes.borrow_mut().insert(*name, "1".to_owned());
+    }
+    let differences = get_differences_against(&document,
+                                              "<p datafoo=\"1\">a</p><svg viewBox=\"1\"></svg>");
+    assert_eq!(differences.len(), 2);
+    // The SVG attributes keep their case.
+    let differences = get_differences_against_with_options(&document,
+                                                           "<p datafoo=\"1\">a</p>\
+                                                            <svg viewBox=\"1\"></svg>",
+                                                           &options);
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].element_name(), "svg");
+}
+
+#[test]
+fn test_namespaced_attributes() {
+    let svg = |href: &str| {
+        format!("<svg><a xlink:href=\"{}\" href=\"b\"><text>a</text></a></svg>", href)
+    };
+    assert!(get_differences(&svg("#a"), &svg("#a")).is_empty());
+    let differences = get_differences(&svg("#a"), &svg("#c"));
+    assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::NodeAttributes { ref elem_attributes, ref opposite_elem_attributes, .. } => {
+            assert_eq!(elem_attributes.get("xlink:href").map(|s| &**s), Some("#a"));
+            assert_eq!(opposite_elem_attributes.get("xlink:href").map(|s| &**s), Some("#c"));
+            assert_eq!(elem_attributes.get("href").map(|s| &**s), Some("b"));
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+}
+
+#[test]
+fn test_ignore_removals() {
+    let options = DiffOptions::default().ignore_removals(true);
+    assert!(get_differences_with_options("<ul><li>a</li><li>b</li></ul><p>c</p>",
+                                         "<ul><li>a</li></ul>", &options).is_empty());
+    let differences = get_differences_with_options("<ul><li>a</li><li>b</li></ul>",
+                                                   "<ul><li>c</li></ul><p>d</p>", &options);
+    assert_eq!(differences.len(), 2);
+    assert!(differences[0].is_node_text());
+    match differences[1] {
+        Difference::NotPresent { elem: None, opposite_elem: Some(ref elem) } => {
+            assert_eq!(elem.element_name, "p");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+}
+
+#[test]
+fn test_treat_nbsp_as_space() {
+    let options = DiffOptions::default().treat_nbsp_as_space(true);
+    assert!(get_differences_with_options("<p>a&nbsp;b</p>", "<p>a b</p>", &options).is_empty());
+    assert_eq!(get_differences_with_options("<p>a&nbsp;b</p>", "<p>a  b</p>", &options).len(), 1);
+    let differences = get_differences("<p>a&nbsp;b</p>", "<p>a b</p>");
+    assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::NodeText { ref elem_text, .. } => assert_eq!(elem_text, "a\u{a0}b"),
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+}
+
+#[test]
+fn test_json_attributes() {
+    let options = DiffOptions::default().json_attributes(["data-props"]);
+    let check = |props1: &str, props2: &str| {
+        get_differences_with_options(&format!("<div data-props='{}'></div>", props1),
+                                     &format!("<div data-props='{}'></div>", props2),
+                                     &options).len()
+    };
+    assert_eq!(check("{\"a\":1,\"b\":{\"c\":[true,null]}}",
+                     " { \"b\": {\"c\": [true, null]}, \"a\": 1.0 } "), 0);
+    assert_eq!(check("{\"a\":\"\\u00e9\\ud83d\\ude00\"}", "{\"a\":\"\u{e9}\u{1f600}\"}"), 0);
+    assert_eq!(check("{\"a\":1}", "{\"a\":2}"), 1);
+    // Arrays are still ordered.
+    assert_eq!(check("[1,2]", "[2,1]"), 1);
+    // Invalid JSON is compared as a string.
+    assert_eq!(check("{\"a\":1", "{\"a\": 1"), 1);
+    assert_eq!(check("{a", "{a"), 0);
+    assert_eq!(check("[+1, .5]", "[1, 0.5]"), 1);
+    // The numbers follow the JSON grammar.
+    for number in &["0", "-0", "10", "1.5", "-2e10", "1E+2", "0.5e-1"] {
+        assert!(json::parse(number).is_some(), "{}", number);
+    }
+    for number in &["+1", ".5", "1.", "01", "-", "-.5", "1e", "1e+", "--1", "0x1"] {
+        assert!(json::parse(number).is_none(), "{}", number);
+    }
+    // Only the given attributes are compared as JSON.
+    assert_eq!(get_differences_with_options("<div title='[1, 2]'></div>",
+                                            "<div title='[1,2]'></div>", &options).len(), 1);
+}
+
+#[test]
+fn test_matches_expected() {
+    assert_eq!(matches_expected("<p>a</p>", "<p>a</p>"), Ok(()));
+    assert_eq!(matches_expected("<p>a</p>", "<p>b</p>"),
+               Err(get_differences("<p>a</p>", "<p>b</p>")));
+    let options = DiffOptions::default().normalize_whitespace(true);
+    assert_eq!(matches_expected_with_options("<p> a</p>", "<p>a </p>", &options), Ok(()));
+}
+
+#[test]
+fn test_normalize_table_sections() {
+    let options = DiffOptions::new().normalize_tables(true);
+    let table1 = "<table><caption>c</caption><colgroup><col><col class=\"x\"></colgroup>\
+                  <thead><tr><th>h</th></tr></thead><tbody><tr><td>a</td></tr></tbody>\
+                  <tfoot><tr><td>f</td></tr></tfoot></table>";
+    let table2 = "<table><tfoot><tr><td>f</td></tr></tfoot><col><col class=\"x\">\
+                  <caption>c</caption><tr><th>h</th></tr><tr><td>a</td></tr></table>";
+    assert!(!get_differences(table1, table2).is_empty());
+    assert!(get_differences_with_options(table1, table2, &options).is_empty());
+    // The `<tfoot>` rows are still compared.
+    let table3 = table2.replace("<td>f</td>", "<td>g</td>");
+    assert_eq!(get_differences_with_options(table1, &table3, &options).len(), 1);
+    // The `span` of a `<colgroup>` without `<col>` matters.
+    assert_eq!(get_differences_with_options("<table><colgroup span=\"2\"></colgroup></table>",
+                                            "<table><colgroup></colgroup></table>",
+                                            &options).len(), 1);
+}
+
+#[test]
+fn test_normalize() {
+    let options = DiffOptions::default().normalize_whitespace(true)
+                                        .ignore_attribute_prefixes(["data-"]);
+    let content1 = "<!doctype HTML><div data-v=\"1\" id=\"&quot;\"><p>a  <b>b</b></p></div>";
+    let content2 = "<!DOCTYPE html>\n<div id='\"'>\n  <p> a <b>b</b></p><!-- c --></div>";
+    assert!(get_differences_with_options(content1, content2, &options).is_empty());
+    let expected = "<!DOCTYPE html><html><head></head><body>\
+                    <div id=\"&quot;\"><p>a<b>b</b></p></div></body></html>";
+    assert_eq!(normalize(content1, &options), expected);
+    assert_eq!(normalize(content2, &options), expected);
+    assert_ne!(normalize("<p>a</p>", &options), normalize("<p>b</p>", &options));
+}
+
+#[test]
+fn test_transparent_inline_tags() {
+    let options = DiffOptions::default().transparent_inline_tags(["span", "b"]);
+    assert!(get_differences_with_options("<p>hello</p>", "<p><span>hello</span></p>",
+                                         &options).is_empty());
+    assert!(get_differences_with_options("<p>Say <b><span>hello</span></b>!</p>",
+                                         "<p>Say hello!</p>", &options).is_empty());
+    assert_eq!(get_differences("<p>hello</p>", "<p><span>hello</span></p>").len(), 1);
+    // The text is still compared.
+    assert_eq!(get_differences_with_options("<p>Say <b>hi</b></p>", "<p>Say hello</p>",
+                                            &options).len(), 1);
+    // Only the elements containing nothing but text are transparent.
+    let differences = get_differences_with_options("<p><span>a<i>b</i></span></p>",
+                                                   "<p>a<i>b</i></p>", &options);
+    assert!(differences[0].is_node_type());
+    assert_eq!(get_differences_with_options("<div>a</div>", "<div><em>a</em></div>",
+                                            &options).len(), 1);
+}
+
+#[test]
+fn test_validate() {
+    assert!(validate("<p id=\"a\"><span id=\"b\"></span></p>").is_empty());
+    let errors = validate("<div id=\"x\"><p id=\"y\"></p><p id=\"x\"></p></div><p id=\"y\"></p>\
+                           <svg><g id=\"x\"></g></svg>");
+    assert_eq!(errors,
+               vec![Validity::DuplicateId {
+                        id: "x".to_owned(),
+                        paths: vec!["/html[0]/body[0]/div[0]".to_owned(),
+                                    "/html[0]/body[0]/div[0]/p[1]".to_owned(),
+                                    "/html[0]/body[0]/svg[0]/g[0]".to_owned()],
+                    },
+                    Validity::DuplicateId {
+                        id: "y".to_owned(),
+                        paths: vec!["/html[0]/body[0]/div[0]/p[0]".to_owned(),
+                                    "/html[0]/body[0]/p[0]".to_owned()],
+                    }]);
+    assert_eq!(errors[1].to_string(),
+               "the id \"y\" is used by 2 elements: /html[0]/body[0]/div[0]/p[0], \
+                /html[0]/body[0]/p[0]");
+}
+
+#[test]
+fn test_ignore_hidden() {
+    let options = DiffOptions::default().ignore_hidden(true);
+    assert!(get_differences_with_options(
+        "<div><p>a</p><p hidden><b>b</b></p></div>",
+        "<div><span style=\"color: red; DISPLAY: None !important\">c</span><p>a</p></div>",
+        &options).is_empty());
+    assert_eq!(get_differences("<p>a</p><p hidden>b</p>", "<p>a</p>").len(), 1);
+    // Only `display: none` hides the elements.
+    assert_eq!(get_differences_with_options("<p>a</p><p style=\"visibility: hidden\">b</p>",
+                                            "<p>a</p>", &options).len(), 1);
+    assert_eq!(get_differences_with_options("<p style=\"display: none; display: block\">b</p>",
+                                            "", &options).len(), 1);
+}
+
+#[test]
+fn test_merge_with_prefix() {
+    let mut differences = merge_with_prefix(get_differences("<p>a</p>", "<p>b</p><i>c</i>"),
+                                            "/header[0]/");
+    differences.extend(merge_with_prefix(get_differences("<!DOCTYPE html>", "<!DOCTYPE svg>"),
+                                         "/main[0]"));
+    assert_eq!(differences.len(), 3);
+    assert_eq!(differences[0].path(), "/header[0]/html[0]/body[0]/p[0]");
+    match differences[1] {
+        Difference::NotPresent { elem: None, opposite_elem: Some(ref elem) } => {
+            assert_eq!(elem.path, "/header[0]/html[0]/body[0]");
+            assert_eq!(elem.depth, 3);
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    // The differences at the document level get the prefix as path.
+    assert_eq!(differences[2].path(), "/main[0]");
+    assert!(differences[2].elements_mut().iter().all(|elem| elem.depth == 1));
+}
+
+#[test]
+fn test_matches_any() {
+    let variants = ["<p>a</p>", "<p class=\"b\">a</p>", "<div><p>c</p></div>"];
+    assert!(matches_any("<p class=\"b\">a</p>", &variants));
+    assert!(!matches_any("<p>b</p>", &variants));
+    assert!(!matches_any("<p>a</p>", &[]));
+    let options = DiffOptions::default().ignore_text(true);
+    assert!(matches_any_with_options("<p>b</p>", &variants, &options));
+
+    let options = DiffOptions::default();
+    assert_eq!(closest_variant("<p class=\"b\">a</p>", &variants, &options), Some((1, vec![])));
+    let candidate = "<div><p>c</p></div><p>x</p>";
+    let (index, differences) = closest_variant(candidate, &variants, &options).unwrap();
+    assert_eq!(index, 2);
+    assert_eq!(differences, get_differences(candidate, variants[2]));
+    assert_eq!(closest_variant("<p>a</p>", &[], &options), None);
+
+    // The attribute order counts in the score.
+    let mut weights = HashMap::new();
+    weights.insert(DifferenceKind::NodeText, 0.5);
+    let options = DiffOptions::default().strict_attribute_order(true).similarity_weights(weights);
+    let candidate = "<p a=\"1\" b=\"2\">x</p><p>y</p>";
+    let variants = ["<p b=\"2\" a=\"1\">x</p><p>y</p>", "<p a=\"1\" b=\"2\">x</p><p>z</p>"];
+    let (index, differences) = closest_variant(candidate, &variants, &options).unwrap();
+    assert_eq!(index, 1);
+    assert!(differences[0].is_node_text());
 }
 
 #[test]
-fn check_attributes_order() {
-    let original = "<div id=\"g\" class=\"foo\"><foo><p></p></foo></div>";
-    let other = "<div class=\"foo\" id=\"g\"><foo><p></p></foo></div>";
+fn test_compare_effective_lang() {
+    let options = DiffOptions::default().compare_effective_lang(true);
+    let check = |content1: &str, content2: &str| {
+        get_differences_with_options(content1, content2, &options).len()
+    };
+    assert_eq!(check("<html lang=\"en\"><body><div><p>a</p></div></body></html>",
+                     "<html><body><div lang=\"EN\"><p>a</p></div></body></html>"), 0);
+    assert_eq!(get_differences("<html lang=\"en\"><body><p>a</p></body></html>",
+                               "<html><body><p lang=\"en\">a</p></body></html>").len(), 1);
+    // A real change of language of a text.
+    assert_eq!(check("<html lang=\"en\"><body><p>a</p><p>b</p></body></html>",
+                     "<html lang=\"en\"><body><p>a</p><p lang=\"fr\">b</p></body></html>"), 1);
+    assert_eq!(check("<html lang=\"en\"><body><p>a</p></body></html>",
+                     "<html><body><p>a</p></body></html>"), 1);
+}
 
-    let differences = get_differences(original, other);
-    assert_eq!(differences.len(), 0, "{:?}", differences);
+#[test]
+fn test_ignore_insignificant_whitespace() {
+    let options = DiffOptions::ignore_insignificant_whitespace();
+    let check = |content1: &str, content2: &str| {
+        get_differences_with_options(content1, content2, &options).len()
+    };
+    assert_eq!(check("<div id=\" a\">\n  <p>Some   text</p>\n</div>",
+                     "<div id=\"a \"><p>Some text\n</p></div>"), 0);
+    assert_eq!(check("<p>Some text</p>", "<p>Some other text</p>"), 1);
+    assert_eq!(check("<p class=\"a  b\">a</p>", "<p class=\"a b\">a</p>"), 1);
+    // The whitespace is significant in `<pre>` and `<textarea>`, even in nested elements.
+    assert_eq!(check("<pre><b>a  b</b></pre>", "<pre><b>a b</b></pre>"), 1);
+    assert_eq!(check("<textarea>a\nb</textarea>", "<textarea>a b</textarea>"), 1);
+    assert_eq!(check("<pre>a</pre>", "<pre>a</pre>"), 0);
+    assert_eq!(get_differences_with_options("<pre>a  b</pre>", "<pre>a b</pre>",
+                                            &DiffOptions::new().normalize_whitespace(true)).len(),
+               0);
 }
 
 #[test]
-fn check_attributes_missing() {
-    let original = "<div id=\"g\" class=\"foo\"><foo><p></p></foo></div>";
-    let other = "<div class=\"foo\"><foo><p></p></foo></div>";
+fn test_path_rewriter() {
+    use std::cell::Cell;
+    use std::rc::Rc;
 
-    let differences = get_differences(original, other);
-    assert_eq!(differences.len(), 1, "{:?}", differences);
-    assert_eq!(differences[0].is_node_attributes(), true, "{:?}", differences[0]);
+    let calls = Rc::new(Cell::new(0));
+    let counter = calls.clone();
+    // One-based positions.
+    let options = DiffOptions::new().path_rewriter(move |path: &str| {
+        counter.set(counter.get() + 1);
+        path.split('/').map(|segment| {
+            match segment.find('[') {
+                Some(pos) => {
+                    let index = segment[pos + 1..segment.len() - 1].parse::<usize>().unwrap();
+                    format!("{}[{}]", &segment[..pos], index + 1)
+                }
+                None => segment.to_owned(),
+            }
+        }).collect::<Vec<_>>().join("/")
+    });
+    let differences = get_differences_with_options("<p>a</p><p>b</p>", "<p>a</p><p>c</p><i></i>",
+                                                   &options);
+    assert_eq!(differences.len(), 2);
+    assert_eq!(differences[0].path(), "/html[1]/body[1]/p[2]");
     match differences[0] {
-        Difference::NodeAttributes { ref elem_attributes,
-                                     ref opposite_elem_attributes,
-                                     .. } => {
-            let mut attributes = HashMap::new();
-            attributes.insert("class".to_owned(), "foo".to_owned());
-            assert_eq!(attributes, *opposite_elem_attributes,
-                       "{:?}/{:?}", opposite_elem_attributes, attributes);
-            attributes.insert("id".to_owned(), "g".to_owned());
-            assert_eq!(attributes, *elem_attributes,
-                       "{:?}/{:?}", elem_attributes, attributes);
+        Difference::NodeText { ref elem, ref opposite_elem, .. } => {
+            assert_eq!(opposite_elem.path, elem.path);
+            assert_eq!(elem.depth, 3);
         }
-        _ => unreachable!(),
+        ref d => panic!("unexpected difference: {:?}", d),
     }
+    assert_eq!(differences[1].path(), "/html[1]/body[1]");
+    assert_eq!(calls.get(), 2);
 }
 
 #[test]
-fn check_child_below() {
-    let original = "<div><foo></foo><a></a><b><c></c></b></div>";
-    let other = "<div><foo></foo><a></a><b><c><d></d></c></b></div>";
+fn test_email() {
+    let template1 = concat!(
+        "<table role=\"presentation\" width=\"100%\" ",
+        "style=\"border-collapse: collapse; background-color: #ffffff;\">",
+        "<tr><td class=\"header dark-bg\" style=\"padding: 10px 20px; color: #333\">",
+        "<a href=\"https://example.com/\" style=\"color:#0066cc;text-decoration:none\">",
+        "Welcome</a>",
+        "</td></tr></table>");
+    let template2 = concat!(
+        "<table role=\"presentation\" width=\" 100% \" ",
+        "style=\"background-color:#ffffff; border-collapse:collapse\">",
+        "<tr><td class=\"dark-bg header\" data-ogsc=\"#ffffff\" ",
+        "style=\"color: #333; PADDING: 10px  20px;\">",
+        "<a href=\"https://example.com/\" style=\"text-decoration: none; color: #0066cc\" ",
+        "data-saferedirecturl=\"https://www.google.com/url?q=https://example.com/\">",
+        "Welcome</a>",
+        "</td></tr></table>");
+    assert!(!get_differences(template1, template2).is_empty());
+    assert!(get_differences_with_options(template1, template2, &DiffOptions::email()).is_empty());
 
-    let differences = get_differences(original, other);
-    assert_eq!(differences.len(), 1, "{:?}", differences);
-    assert_eq!(differences[0].is_not_present(), true, "{:?}", differences[0]);
+    let template3 = template2.replace("color: #0066cc", "color: #cc0000");
+    let differences = get_differences_with_options(template1, &template3, &DiffOptions::email());
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].element_name(), "a");
+    // The last declaration of a property wins.
+    assert!(get_differences_with_options("<p style=\"color: red; color: blue\">a</p>",
+                                         "<p style=\"color: blue\">a</p>",
+                                         &DiffOptions::email()).is_empty());
 }
 
 #[test]
-fn test_path() {
-    let original = "<div><foo></foo><a></a><b><c></c></b></div>";
-    let other = "<div><foo></foo><a></a><b><c><d></d></c></b></div>";
+fn test_numeric_list_attributes() {
+    let options = DiffOptions::new().numeric_list_attributes(["viewBox", "points"])
+                                    .numeric_tolerance(0.001);
+    let check = |content1: &str, content2: &str| {
+        get_differences_with_options(content1, content2, &options).len()
+    };
+    assert_eq!(check("<svg viewBox=\"0 0 100 100\"></svg>",
+                     "<svg viewBox=\" 0,0, 100.0  1e2 \"></svg>"), 0);
+    assert_eq!(check("<svg><polygon points=\"0,0 1,1 2.0005,0\"/></svg>",
+                     "<svg><polygon points=\"0 0, 1 1, 2 0\"/></svg>"), 0);
+    assert_eq!(check("<svg viewBox=\"0 0 100 100\"></svg>",
+                     "<svg viewBox=\"0 0 100 100.01\"></svg>"), 1);
+    assert_eq!(check("<svg viewBox=\"0 0 100\"></svg>", "<svg viewBox=\"0 0 100 0\"></svg>"),
+               1);
+    // The tokens which aren't numbers are compared as they are.
+    assert_eq!(check("<svg viewBox=\"0 0 a\"></svg>", "<svg viewBox=\"0,0,a\"></svg>"), 0);
+    assert_eq!(check("<svg viewBox=\"0 0 a\"></svg>", "<svg viewBox=\"0 0 b\"></svg>"), 1);
+    // Without tolerance, the numbers still have to be equal.
+    let options = DiffOptions::new().numeric_list_attributes(["points"]);
+    assert_eq!(get_differences_with_options("<svg><polyline points=\"1,2\"/></svg>",
+                                            "<svg><polyline points=\"1.0 2.0001\"/></svg>",
+                                            &options).len(), 1);
+}
 
-    let differences = get_differences(original, other);
-    assert_eq!(differences.len(), 1, "{:?}", differences);
-    assert_eq!(differences[0].is_not_present(), true, "{:?}", differences[0]);
+#[test]
+fn test_stable_id() {
+    let ids = |content1: &str, content2: &str| {
+        get_differences(content1, content2).iter().map(|d| d.stable_id()).collect::<Vec<_>>()
+    };
+    let content1 = "<div a=\"1\" b=\"2\" c=\"3\"><p>x</p></div><p>y</p><i>z</i>";
+    let content2 = "<div c=\"3\" b=\"2\" a=\"1\"><p>x</p></div><p>w</p>";
+    let first = ids(content1, content2);
+    assert_eq!(first.len(), 2);
+    assert_ne!(first[0], first[1]);
+    for _ in 0..5 {
+        assert_eq!(ids(content1, content2), first);
+    }
+    // The side of a `NotPresent` difference matters.
+    assert_ne!(ids("<p>a</p>", ""), ids("", "<p>a</p>"));
+    // So does the path.
+    assert_ne!(ids("<p>a</p>", "<p>b</p>"), ids("<div><p>a</p></div>", "<div><p>b</p></div>"));
+    // And the attributes of elements which only exist in a table.
+    let table = |cell: &str| format!("<table><tr>{}</tr></table>", cell);
+    assert_ne!(ids(&table("<td class=\"x\">1</td>"), &table("<th class=\"x\">1</th>")),
+               ids(&table("<td class=\"y\">1</td>"), &table("<th class=\"y\">1</th>")));
+}
+
+#[test]
+fn test_trim_path_prefix() {
+    let options = DiffOptions::new().trim_path_prefix(&["html", "body"]);
+    let differences = get_differences_with_options("<title>a</title><div><p>a</p></div>b",
+                                                   "<title>b</title><div><p>b</p></div>c",
+                                                   &options);
+    assert_eq!(differences.len(), 3);
+    // Not below `<body>`, kept as is.
+    assert_eq!(differences[0].path(), "/html[0]/head[0]/title[0]");
+    assert_eq!(differences[1].path(), "/div[0]/p[0]");
+    assert_eq!(differences[2].path(), "");
+    // The depth isn't changed.
+    match differences[1] {
+        Difference::NodeText { ref elem, ref opposite_elem, .. } => {
+            assert_eq!(elem.depth, 4);
+            assert_eq!(opposite_elem.path, "/div[0]/p[0]");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+
+    let options = options.path_rewriter(|path: &str| format!("#{}", path));
+    let differences = get_differences_with_options("<div>a</div>", "<div>b</div>", &options);
+    assert_eq!(differences[0].path(), "#/div[0]");
+}
+
+#[test]
+fn test_text_differences() {
+    let texts = text_differences("<ul><li>One</li><li>Two\n  items</li></ul><p>Three</p>",
+                                 "<ul><li>Uno</li><li>Dos items</li></ul><div>Three</div>");
+    assert_eq!(texts, vec![
+        ("/html[0]/body[0]/ul[0]/li[0]".to_owned(), "One".to_owned(), "Uno".to_owned()),
+        ("/html[0]/body[0]/ul[0]/li[1]".to_owned(), "Two items".to_owned(),
+         "Dos items".to_owned()),
+    ]);
+    assert!(text_differences("<p>a  b</p>", "<p>a\nb</p>").is_empty());
+}
+
+#[test]
+fn test_minification_tolerant() {
+    let source = r#"<!DOCTYPE html>
+<html lang="en">
+  <head>
+    <meta charset="utf-8">
+    <title>Sample page</title>
+    <!-- Styles -->
+    <link rel="stylesheet" href="style.css">
+  </head>
+  <body>
+    <header class="site-header  dark">
+      <h1>Welcome</h1>
+    </header>
+    <ul id="menu">
+      <li><a href="/" title="Home page">Home</a></li>
+      <li><a href="/about">About <em>us</em></a></li>
+    </ul>
+    <p style="color: red; margin: 0 auto;">
+      Some   text
+      on several lines.
+    </p>
+    <pre>  keep
+  this</pre>
+    <form>
+      <input type="text" name="q" disabled="disabled">
+      <select name="s"><option value="1" selected="selected">One</option></select>
+    </form>
+  </body>
+</html>
+"#;
+    // What html-minifier-terser outputs with `collapseWhitespace`, `removeComments`,
+    // `removeOptionalTags`, `removeAttributeQuotes`, `collapseBooleanAttributes`,
+    // `removeRedundantAttributes`, `sortAttributes` and `minifyCSS`.
+    let minified = concat!("<!doctype html><html lang=en><meta charset=utf-8>",
+                           "<title>Sample page</title><link href=style.css rel=stylesheet>",
+                           "<header class=\"site-header dark\"><h1>Welcome</h1></header>",
+                           "<ul id=menu><li><a href=/ title=\"Home page\">Home</a>",
+                           "<li><a href=/about>About <em>us</em></a></ul>",
+                           "<p style=\"margin:0 auto;color:red\">Some text on several lines.",
+                           "<pre>  keep\n  this</pre>",
+                           "<form><input disabled name=q><select name=s>",
+                           "<option selected value=1>One</select></form>");
+    let options = DiffOptions::minification_tolerant();
+    assert_eq!(get_differences_with_options(source, minified, &options), Vec::new());
+    assert!(!get_differences(source, minified).is_empty());
+    // A minifier changing the semantics is still caught.
+    let broken = minified.replace("<pre>  keep\n  this</pre>", "<pre>keep this</pre>");
+    assert_eq!(get_differences_with_options(source, &broken, &options).len(), 1);
+}
+
+#[test]
+fn test_min_subtree_size() {
+    let content1 = "<div><p>a</p><ul><li>b</li><li>c</li></ul></div><b>d</b><br>";
+    let content2 = "<div><p>a</p></div><i>d</i>";
+    assert_eq!(get_differences(content1, content2).len(), 3);
+    let options = DiffOptions::new().min_subtree_size(3);
+    let differences = get_differences_with_options(content1, content2, &options);
+    assert_eq!(differences.len(), 1);
     match differences[0] {
-        Difference::NotPresent { ref elem, ref opposite_elem } => {
-            assert_eq!(elem.is_none(), true, "{:?}", elem);
-            assert_eq!(opposite_elem.is_some(), true, "{:?}", opposite_elem);
-            assert_eq!(*opposite_elem,
-                       Some(ElementInformation {
-                           element_name: "d".to_owned(),
-                           element_content: "<d></d>".to_owned(),
-                           path: "/html[0]/body[0]/div[0]/b[0]/c[0]".to_owned(),
-                       }),
-                       "{:?}", opposite_elem);
+        Difference::NotPresent { elem: Some(ref elem), opposite_elem: None } => {
+            assert_eq!(elem.element_name, "ul");
         }
-        _ => unreachable!(),
+        ref d => panic!("unexpected difference: {:?}", d),
     }
-    assert_eq!(differences[0].is_not_present(), true, "{:?}", differences[0]);
+    // The small subtrees aren't compared, the other differences are still reported.
+    let differences = get_differences_with_options("<b>d</b><p>x</p>", "<i>e</i><p>y</p>",
+                                                   &options);
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].kind(), DifferenceKind::NodeText);
+    // A tag change counts the descendants of both sides.
+    assert_eq!(get_differences_with_options("<b></b>", "<i><p>a</p><p>b</p></i>",
+                                            &options).len(), 1);
+    let options = options.unordered(true);
+    assert_eq!(get_differences_with_options(content1, content2, &options).len(), 1);
 }
 
 #[test]
-fn test_issue_6() {
-    let a = "<!-- .basic -->\n<span class=\"why\">chunky bacon</span>\n\n<!-- .emphasis -->\n\
-             <em>chunky bacon</em>\n\n<!-- .emphasis_with_role -->\n<em class=\"why\">chunky bacon\
-             </em>\n\n<!-- .strong -->\n<strong>chunky bacon</strong>\n\n<!-- .strong_with_role \
-             -->\n<strong class=\"why\">chunky bacon</strong>\n\n<!-- .monospaced -->\n<code>\
-             hello world!</code>\n\n<!-- .monospaced_with_role -->\n<code class=\"why\">hello \
-             world!</code>\n\n<!-- .superscript -->\n<sup>super</sup>chunky bacon\n\n<!-- \
-             .superscript_with_role -->\n<sup class=\"why\">super</sup>chunky bacon\n\n<!-- \
-             .subscript -->\n<sub>sub</sub>chunky bacon\n\n<!-- .subscript_with_role -->\n<sub \
-             class=\"why\">sub</sub>chunky bacon\n\n<!-- .mark -->\n<mark>chunky bacon</mark>\n\n\
-             <!-- .double -->\n“chunky bacon”\n\n<!-- .double_with_role -->\n<span class=\"why\">\
-             “chunky bacon”</span>\n\n<!-- .single -->\n‘chunky bacon’\n\n<!-- .single_with_role \
-             -->\n<span class=\"why\">‘chunky bacon’</span>\n\n<!-- .asciimath -->\n\\$sqrt(4) = 2\
-             \\$\n\n<!-- .latexmath -->\n\\($C = \\alpha + \\beta Y^{\\gamma} + \\epsilon$\\)\n\n\
-             <!-- .with_id -->\n<a id=\"why\"></a><em>chunky bacon</em>\n\n<!-- \
-             .mixed_monospace_bold_italic -->\n<code><strong><em>monospace bold italic phrase</em>\
-             </strong></code> and le<code><strong><em>tt</em></strong></code>ers\n";
-    let b = "<mark></mark><div class=\"paragraph\"><p>chunky bacon</p></div><mark></mark>";
-    let _ = get_differences(a, b);
+fn test_bidirectional_diff() {
+    let content1 = "<div><p>a</p><p>b</p></div><span>x</span>";
+    let content2 = "<div><p>c</p></div><span>x</span><i>y</i><b>z</b>";
+    let bidiff = bidirectional_diff(content1, content2);
+    assert_eq!(bidiff.only_in_left.iter().map(|e| &*e.element_content).collect::<Vec<_>>(),
+               vec!["<p>b</p>"]);
+    assert_eq!(bidiff.only_in_right.iter().map(|e| &*e.element_name).collect::<Vec<_>>(),
+               vec!["i", "b"]);
+    assert_eq!(bidiff.changed.len(), 1);
+    assert_eq!(bidiff.changed[0].kind(), DifferenceKind::NodeText);
+    // Nothing is lost compared to the flat list.
+    let differences = get_differences(content1, content2);
+    assert_eq!(differences.len(), bidiff.only_in_left.len() + bidiff.only_in_right.len() +
+                                  bidiff.changed.len());
+    assert_eq!(BiDiff::from_differences(differences), bidiff);
+    assert!(bidirectional_diff("<p>a</p>", "<p>a</p>").is_empty());
 }
 
 #[test]
-fn test_attributes() {
-    let a = r#"<span class="toto"></span>"#;
-    let b = r#"<span class="tata"></span>"#;
-    let differences = get_differences(a, b);
+fn test_strict_attribute_order() {
+    let content1 = "<div id=\"a\" class=\"b\"><p title='x' lang=en hidden>t</p></div>\
+                    <script>var s = '<b d=\"2\" c=\"1\">';</script><b c=\"1\" d=\"2\"></b>";
+    let content2 = "<div class=\"b\" id=\"a\"><p title='x' lang=en hidden>t</p></div>\
+                    <script>var s = '<b d=\"2\" c=\"1\">';</script><b d=\"2\" c=\"1\"></b>";
+    assert!(get_differences(content1, content2).is_empty());
+    let options = DiffOptions::new().strict_attribute_order(true);
+    let differences = get_differences_with_options(content1, content2, &options);
+    assert_eq!(differences.len(), 2);
+    match differences[0] {
+        Difference::AttributeOrder { ref elem, ref elem_attributes,
+                                     ref opposite_elem_attributes, .. } => {
+            assert_eq!(elem.element_name, "div");
+            assert_eq!(elem.path, "/html[0]/body[0]");
+            assert_eq!(elem_attributes, &["id", "class"]);
+            assert_eq!(opposite_elem_attributes, &["class", "id"]);
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    // The tag in the script isn't taken for the one of `<b>`.
+    assert_eq!(differences[1].to_string(),
+               "/html[0]/body[0] => [Attribute order differs in \"b\"]: expected [\"c\", \"d\"], \
+                found [\"d\", \"c\"]");
+    // The ignored attributes don't count.
+    let options = options.ignore_attribute_prefixes(["id"]);
+    assert_eq!(get_differences_with_options(content1, content2, &options).len(), 1);
+
+    let differences = get_differences_with_options(
+        "<svg viewBox=\"0 0 1 1\" width=\"1\"></svg>",
+        "<svg width=\"1\" viewBox=\"0 0 1 1\"></svg>",
+        &DiffOptions::new().strict_attribute_order(true));
     assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::AttributeOrder { ref elem_attributes, .. } => {
+            assert_eq!(elem_attributes, &["viewBox", "width"]);
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+}
 
-    let a = r#"<span foo="a" class="toto"></span>"#;
-    let b = r#"<span class="toto" foo="a"></span>"#;
-    let differences = get_differences(a, b);
-    assert_eq!(differences.len(), 0);
+#[test]
+fn test_not_present_display() {
+    let differences = get_differences("<p>a</p><ul></ul>", "<p>a</p>");
+    assert_eq!(differences.iter().map(|d| d.to_string()).collect::<Vec<_>>(), vec![
+        "/html[0]/body[0] => [One element is missing]: element <ul> present in left but \
+         missing in right",
+    ]);
+    let differences = get_differences("<p>a</p>", "<p>a</p><ul></ul>b");
+    assert_eq!(differences.iter().map(|d| d.to_string()).collect::<Vec<_>>(), vec![
+        "/html[0]/body[0] => [Unexpected element]: element <ul> present in right but \
+         missing in left",
+        "/html[0]/body[0] => [Unexpected element]: text \"b\" present in right but missing \
+         in left",
+    ]);
+    let diff = Difference::NotPresent { elem: None, opposite_elem: None };
+    assert_eq!(diff.to_string(), " => [Invalid difference]: no element on any side");
+    // It has an empty path.
+    assert_eq!(diff.element_name(), "");
+    assert_eq!(diff.path(), "");
+    assert_eq!(diff.render(Verbosity::Terse), " => [Invalid difference]");
+    assert!(diff.render(Verbosity::Verbose).starts_with("[Invalid difference]\n    path: "));
+    assert!(path_glob_matches(&diff, "/**"));
+    assert_eq!(common_ancestor_path(&diff, &differences[0]), "");
+    assert!(build_difference_tree(&[diff]).children.is_empty());
 }
 
 #[test]
-fn test_path_logic() {
-    use Difference::NodeText;
+fn test_ignore_tracking() {
+    let content1 = "<p>a</p>\
+                    <img src=\"/spacer.gif\" style=\"width: 1px; height: 1px\">\
+                    <script async src=\"https://www.googletagmanager.com/gtag/js?id=1\">\
+                    </script>\
+                    <noscript><iframe src=\"https://www.googletagmanager.com/ns.html\"></iframe>\
+                    </noscript><img src=\"logo.png\" width=\"100\" height=\"1\">";
+    let content2 = "<p>a</p>\
+                    <script>(function() { var s = 'https://mc.yandex.ru/metrika/tag.js'; })();\
+                    </script><img src=\"logo.png\" width=\"100\" height=\"1\">\
+                    <iframe src=\"https://bat.bing.com/action/0?ti=2\"></iframe>";
+    assert!(!get_differences(content1, content2).is_empty());
+    assert!(get_differences_with_options(content1, content2,
+                                         &DiffOptions::ignore_tracking()).is_empty());
+    // The other elements are still compared.
+    let differences = get_differences_with_options("<img src=\"a.png\" width=\"2\" height=\"1\">",
+                                                   "", &DiffOptions::ignore_tracking());
+    assert_eq!(differences.len(), 1);
+}
 
-    let a = r#"<div>
-    <img src="...">
-    <div>
-        <p>here will be changed content</p>
-    </div>
-</div>"#;
-    let b = r#"<div>
-    <img src="...">
-    <div>
-        <p>here will be changed</p>
-    </div>
-</div>"#;
+#[test]
+fn test_difference_hash() {
+    use std::collections::HashSet;
 
-    let differences = get_differences(a, b);
-    assert_eq!(differences,
-               vec![NodeText {
-                        elem: ElementInformation {
-                                  element_name: String::new(),
-                                  element_content: String::new(),
-                                  path: "/html[0]/body[0]/div[0]/div[0]/p[0]".to_owned(),
-                              },
-                        elem_text: "here will be changed content".to_owned(),
-                        opposite_elem: ElementInformation {
-                                           element_name: String::new(),
-                                           element_content: String::new(),
-                                           path: "/html[0]/body[0]/div[0]/div[0]/p[0]".to_owned(),
-                                       },
-                        opposite_elem_text: "here will be changed".to_owned(),
-                    }]);
+    let content1 = "<p a=\"1\" b=\"2\" c=\"3\" d=\"4\">x</p><i>y</i>";
+    let content2 = "<p>x</p><i>z</i>";
+    let mut set = HashSet::new();
+    // The attributes are in a different order in the `HashMap`s of each run.
+    for _ in 0..10 {
+        set.extend(get_differences(content1, content2));
+    }
+    assert_eq!(set.len(), 2);
+    let other = get_differences("<p>x</p><i>y</i>", "<p>x</p><i>z</i>").into_iter()
+                                                                       .collect::<HashSet<_>>();
+    assert_eq!(set.intersection(&other).count(), 1);
+    assert_eq!(set.union(&other).count(), 2);
+    // The paths are part of the equality.
+    let moved = get_differences("<div><i>y</i></div>", "<div><i>z</i></div>");
+    assert!(!set.contains(&moved[0]));
 }
 
 #[test]
-fn test_path_logic2() {
-    use Difference::NodeText;
+fn test_options_as_set() {
+    let content1 = "<select><option value=\"1\">One</option><option value=\"2\">Two</option>\
+                    <option>Three</option><optgroup><option>a</option><option>b</option>\
+                    </optgroup></select>";
+    let content2 = "<select><option>Three</option><option value=\"2\">Deux</option>\
+                    <option value=\"4\">Four</option><optgroup><option>b</option>\
+                    <option>a</option></optgroup></select>";
+    assert!(get_differences(content1, content2).len() > 3);
+    let options = DiffOptions::new().options_as_set(true);
+    let differences = get_differences_with_options(content1, content2, &options);
+    assert_eq!(differences.len(), 3);
+    match differences[0] {
+        Difference::NotPresent { elem: Some(ref elem), opposite_elem: None } => {
+            assert_eq!(elem.element_content, "<option value=\"1\">One</option>");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    match differences[1] {
+        Difference::NodeText { ref elem, ref elem_text, ref opposite_elem_text, .. } => {
+            assert_eq!(elem.path, "/html[0]/body[0]/select[0]/option[1]");
+            assert_eq!(elem_text, "Two");
+            assert_eq!(opposite_elem_text, "Deux");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    match differences[2] {
+        Difference::NotPresent { elem: None, opposite_elem: Some(ref elem) } => {
+            assert_eq!(elem.element_content, "<option value=\"4\">Four</option>");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    // The same values in a `<datalist>`, the attributes are still compared.
+    let differences = get_differences_with_options(
+        "<datalist><option value=\"a\"></option><option value=\"b\"></option></datalist>",
+        "<datalist><option value=\"b\" label=\"B\"></option><option value=\"a\"></option>\
+         </datalist>",
+        &options);
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].kind(), DifferenceKind::NodeAttributes);
+}
 
-    let a = r#"<div>
-    <div></div>
-    <div>
-        <p>here will be changed content</p>
-    </div>
-</div>"#;
-    let b = r#"<div>
-    <div></div>
-    <div>
-        <p>here will be changed</p>
-    </div>
-</div>"#;
+#[test]
+fn test_is_structurally_equal() {
+    use std::cell::Cell;
+    use std::rc::Rc;
 
-    let differences = get_differences(a, b);
-    assert_eq!(differences,
-               vec![NodeText {
-                        elem: ElementInformation {
-                                  element_name: String::new(),
-                                  element_content: String::new(),
-                                  path: "/html[0]/body[0]/div[0]/div[1]/p[0]".to_owned(),
-                              },
-                        elem_text: "here will be changed content".to_owned(),
-                        opposite_elem: ElementInformation {
-                                           element_name: String::new(),
-                                           element_content: String::new(),
-                                           path: "/html[0]/body[0]/div[0]/div[1]/p[0]".to_owned(),
-                                       },
-                        opposite_elem_text: "here will be changed".to_owned(),
-                    }]);
+    let options = DiffOptions::new();
+    // Only cosmetic differences.
+    assert!(is_structurally_equal("<div id=\"a\"><p>Some\n text</p>x</div>",
+                                  "<div id=\"b\" class=\"c\"><p>Some text!</p></div>",
+                                  &options));
+    assert!(!is_structurally_equal("<div><p>a</p></div>", "<div><p>a</p><p>b</p></div>",
+                                   &options));
+    assert!(!is_structurally_equal("<p>a</p><b>b</b>", "<p>b</p><i>b</i>",
+                                   &options.clone().max_differences(1)));
+    // The walk stops at the first structural difference, the `<p>`s aren't compared.
+    let matches = Rc::new(Cell::new(0));
+    let counter = matches.clone();
+    let options = options.on_match(move |_, _, _| counter.set(counter.get() + 1));
+    assert!(!is_structurally_equal("<b></b><p>a</p><p>b</p>", "<i></i><p>a</p><p>b</p>",
+                                   &options));
+    // `<html>`, `<head>` and `<body>`.
+    assert_eq!(matches.get(), 3);
+    // The children of elements whose attributes differ are still compared.
+    assert!(!is_structurally_equal("<div class=\"a\"><p>x</p></div>",
+                                   "<div class=\"b\"><span>x</span></div>", &options));
+    assert!(is_structurally_equal("<div class=\"a\"><p>x</p></div>",
+                                  "<div class=\"b\"><p>y</p></div>", &options));
+    // The `<div>`s aren't matching elements, the `<p>`s of the last comparison are.
+    assert_eq!(matches.get(), 10);
 }
 
 #[test]
-fn test_unicode() {
-    let a = r#"<div><div></div>០</div<"#;
-    let b = r#"<div><div></div>០</div>"#;
-    let differences = get_differences(a, b);
-    assert_eq!(differences.len(), 0);
+fn test_opaque_text_over() {
+    let data1 = format!("<script>var data = {:?};</script><p>a  b</p>", vec![1; 1000]);
+    let data2 = format!("<script>var data = {:?};</script><p>a  b</p>", vec![2; 1000]);
+    let options = DiffOptions::new().opaque_text_over(20).normalize_whitespace(true);
+    assert!(get_differences_with_options(&data1, &data1, &options).is_empty());
+    let differences = get_differences_with_options(&data1, &data2, &options);
+    assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::NodeText { ref elem_text, ref opposite_elem_text, .. } => {
+            assert_eq!(elem_text, "var data = [1, 1, 1,...");
+            assert_eq!(opposite_elem_text, "var data = [2, 2, 2,...");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    // The opaque texts aren't normalized, the short ones still are.
+    assert_eq!(get_differences_with_options("<p>a long text  with spaces</p><p>a  b</p>",
+                                            "<p>a long text with spaces</p><p>a b</p>",
+                                            &options).len(), 1);
+    // The length is counted in characters.
+    assert_eq!(get_differences_with_options("<p>éééééééééééééééééééé</p>",
+                                            "<p>eééééééééééééééééééé</p>",
+                                            &options)[0].to_string(),
+               "/html[0]/body[0]/p[0] => [Texts differ]: expected \"éééééééééééééééééééé\", \
+                found \"eééééééééééééééééééé\"");
+}
+
+#[test]
+fn test_xml_strict() {
+    let rss = |link: &str, guid: &str, date: &str| {
+        format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <rss version=\"2.0\"><channel><title>Feed</title><link>{}</link>\
+                 <item><title>One</title><guid {}=\"false\"/>\
+                 <description><![CDATA[<p>First</p>]]></description>\
+                 <{2}>Mon, 12 Oct 2026</{2}></item></channel></rss>", link, guid, date)
+    };
+    let rss1 = rss("https://example.org/", "isPermaLink", "pubDate");
+    let options = DiffOptions::xml_strict();
+    assert!(get_differences_with_options(&rss1, &rss1, &options).is_empty());
+    let rss2 = rss("https://example.com/", "ispermalink", "pubdate");
+    let differences = get_differences_with_options(&rss1, &rss2, &options);
+    assert_eq!(differences.len(), 3);
+    // The text of `<link>` stays in it.
+    assert_eq!(differences[0].to_string(),
+               "/rss[0]/channel[0]/link[0] => [Texts differ]: expected \"https://example.org/\", \
+                found \"https://example.com/\"");
+    // `<guid/>` doesn't contain the next elements.
+    match differences[1] {
+        Difference::NodeAttributes { ref elem, ref elem_attributes,
+                                     ref opposite_elem_attributes, .. } => {
+            assert_eq!(elem.element_name, "guid");
+            assert_eq!(elem.path, "/rss[0]/channel[0]/item[0]");
+            assert_eq!(elem_attributes.get("isPermaLink").map(|v| &**v), Some("false"));
+            assert_eq!(opposite_elem_attributes.get("ispermalink").map(|v| &**v), Some("false"));
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    match differences[2] {
+        Difference::NodeName { ref elem, ref opposite_elem } => {
+            assert_eq!(elem.element_name, "pubDate");
+            assert_eq!(opposite_elem.element_name, "pubdate");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    // Parsed as HTML, `<link>` is a void element and the names are case-insensitive.
+    let differences = get_differences(&rss1, &rss2);
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].path(), "/html[0]/body[0]/rss[0]/channel[0]");
+
+    // The items are compared in order.
+    let items = |first: &str, second: &str| {
+        format!("<rss><channel><item><title>{}</title></item><item><title>{}</title></item>\
+                 </channel></rss>", first, second)
+    };
+    let differences = get_differences_with_options(&items("One", "Two"), &items("Two", "One"),
+                                                   &options);
+    assert_eq!(differences.len(), 2);
+    assert_eq!(differences[0].path(), "/rss[0]/channel[0]/item[0]/title[0]");
+}
+
+#[test]
+fn test_walk_differences_with_nodes() {
+    let mut found = Vec::new();
+    walk_differences_with_nodes("<p id=\"a\">x</p><ul><li>1</li></ul>",
+                                "<p id=\"b\">x</p><ul><li>2</li><li>3</li></ul>",
+                                &DiffOptions::new(), |diff, node1, node2| {
+        let describe = |node: Option<&NodeRef>| {
+            node.map(|n| match n.as_element() {
+                Some(e) => e.name.local.to_string(),
+                None => n.text_contents(),
+            })
+        };
+        found.push((diff.kind(), describe(node1), describe(node2)));
+    });
+    assert_eq!(found, vec![
+        (DifferenceKind::NodeAttributes, Some("p".to_owned()), Some("p".to_owned())),
+        (DifferenceKind::NodeText, Some("1".to_owned()), Some("2".to_owned())),
+        (DifferenceKind::NotPresent, None, Some("li".to_owned())),
+    ]);
+    // The nodes are in the trees, with their ancestors.
+    let mut ancestors = 0;
+    walk_differences_with_nodes("<div><b>a</b></div>", "<div><i>a</i></div>",
+                                &DiffOptions::new(), |_, node1, node2| {
+        assert_eq!(node1.unwrap().parent().unwrap().as_element().unwrap().name.local, *"div");
+        ancestors = node2.unwrap().ancestors().count();
+    });
+    // `<div>`, `<body>`, `<html>` and the document.
+    assert_eq!(ancestors, 4);
+    // The number of differences is still limited.
+    let mut count = 0;
+    walk_differences_with_nodes("<p>a</p><p>b</p>", "<p>c</p><p>d</p>",
+                                &DiffOptions::new().max_differences(1), |_, _, _| count += 1);
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn test_elements_only() {
+    let content1 = "<div>Some text<img src=\"a.png\"> and <br><span>x</span></div>";
+    let content2 = "<div><img src=\"a.png\"><br>other text<span>y</span></div>";
+    // With `ignore_text`, the texts still take their place among the children.
+    let options = DiffOptions::new().ignore_text(true);
+    assert!(!get_differences_with_options(content1, content2, &options).is_empty());
+    let options = DiffOptions::new().elements_only(true);
+    assert!(get_differences_with_options(content1, content2, &options).is_empty());
+    assert!(get_differences_with_options("<!DOCTYPE html><p></p>", "<p></p>",
+                                         &options).is_empty());
+    let differences = get_differences_with_options(
+        "<div>a<img src=\"a.png\" alt=\"x\"></div>",
+        "<div><img src=\"b.png\" alt=\"x\">b</div>",
+        &options.ignore_attribute_prefixes(["alt"]));
+    assert_eq!(differences.len(), 1);
+    assert!(differences[0].is_node_attributes());
+    assert_eq!(differences[0].path(), "/html[0]/body[0]/div[0]");
+}
+
+#[test]
+fn test_common_ancestor_path() {
+    let differences = get_differences("<div><p>a</p><p>b</p></div><div><p>c</p></div>",
+                                      "<div><p>x</p><p>y</p></div><div><p>z</p></div>");
+    assert_eq!(differences.len(), 3);
+    assert_eq!(common_ancestor_path(&differences[0], &differences[0]),
+               "/html[0]/body[0]/div[0]/p[0]");
+    assert_eq!(common_ancestor_path(&differences[0], &differences[1]), "/html[0]/body[0]/div[0]");
+    // `div[0]` and `div[1]` are different segments.
+    assert_eq!(common_ancestor_path(&differences[1], &differences[2]), "/html[0]/body[0]");
+    // The doctype is at the document level.
+    let doctype = get_differences("<!DOCTYPE html><p>a</p>",
+                                  "<!DOCTYPE html PUBLIC \"-//W3C//DTD HTML 4.01//EN\"><p>a</p>");
+    assert_eq!(doctype[0].path(), "");
+    assert_eq!(common_ancestor_path(&doctype[0], &differences[0]), "");
+    // The paths without common segments, like the trimmed ones.
+    let options = DiffOptions::new().trim_path_prefix(&["html", "body"]);
+    let trimmed = get_differences_with_options("<div><p>a</p></div><ul><li>b</li></ul>",
+                                               "<div><p>x</p></div><ul><li>y</li></ul>",
+                                               &options);
+    assert_eq!(common_ancestor_path(&trimmed[0], &trimmed[1]), "");
+}
+
+#[test]
+fn test_br_as_newline() {
+    let content1 = "<p>Dear John,<br>Thanks.<br><b>Bye</b></p>";
+    let content2 = "<p>Dear John,\nThanks.\n<b>Bye</b></p>";
+    assert!(!get_differences(content1, content2).is_empty());
+    let options = DiffOptions::new().br_as_newline(true);
+    assert!(get_differences_with_options(content1, content2, &options).is_empty());
+    let differences = get_differences_with_options("<p>a<br>b</p>", "<p>a<br>c</p>", &options);
+    assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::NodeText { ref elem, ref elem_text, ref opposite_elem_text, .. } => {
+            assert_eq!(elem.path, "/html[0]/body[0]/p[0]");
+            assert_eq!(elem.parent_content.as_deref(), Some("<p>"));
+            assert_eq!(elem_text, "a\nb");
+            assert_eq!(opposite_elem_text, "a\nc");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    // Without the merge, the line break is a text of its own.
+    let options = options.collapse_text_nodes(false);
+    assert!(!get_differences_with_options("<p>a<br>b</p>", "<p>a\nb</p>", &options).is_empty());
+}
+
+#[test]
+fn test_heading_outline_differences() {
+    let content1 = "<h1>Guide</h1><p>intro</p><h2>Install</h2><h3>From  source</h3>\
+                    <h2>Usage</h2><h2>FAQ</h2>";
+    let content2 = "<header><h1>Guide</h1></header><p>other intro</p><h2>Install</h2>\
+                    <h2>From source</h2><h2>Using it</h2><h2>FAQ</h2><h2>License</h2>";
+    assert!(heading_outline_differences(content1, content1).is_empty());
+    let differences = heading_outline_differences(content1, content2);
+    assert_eq!(differences.len(), 3);
+    // The demoted heading.
+    match differences[0] {
+        Difference::NodeName { ref elem, ref opposite_elem } => {
+            assert_eq!(elem.element_name, "h3");
+            assert_eq!(elem.path, "/html[0]/body[0]");
+            assert_eq!(opposite_elem.element_name, "h2");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    assert_eq!(differences[1].to_string(),
+               "/html[0]/body[0]/h2[1] => [Texts differ]: expected \"Usage\", found \"Using it\"");
+    assert_eq!(differences[2].to_string(),
+               "/html[0]/body[0] => [Unexpected element]: element <h2> present in right but \
+                missing in left");
+    // The removed heading.
+    let differences = heading_outline_differences("<h1>A</h1><h2>B</h2><h2>C</h2>",
+                                                  "<h1>A</h1><h2>C</h2>");
+    assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::NotPresent { elem: Some(ref elem), opposite_elem: None } => {
+            assert_eq!(elem.element_content, "<h2>B</h2>");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+}
+
+#[test]
+fn test_select_state() {
+    let build = "<div class=\"toggle\"><span>Settings</span>\
+                 <button data-state=\"open\">Close</button>\
+                 <button data-state=\"closed\">Open</button>\
+                 <ul data-state=\"open\"><li data-state=\"closed\">never</li><li>Item</li></ul>\
+                 </div>";
+    let open = "<div class=\"toggle\"><span>Settings</span><button data-state=\"open\">Close\
+                </button><ul data-state=\"open\"><li>Item</li></ul></div>";
+    let closed = "<div class=\"toggle\"><span>Settings</span><button data-state=\"closed\">Open\
+                  </button></div>";
+    assert!(!get_differences(build, open).is_empty());
+    assert!(get_differences_with_options(build, open,
+                                         &DiffOptions::new().select_state("data-state", "open"))
+                .is_empty());
+    let options = DiffOptions::new().select_state("data-state", "closed");
+    assert!(get_differences_with_options(build, closed, &options).is_empty());
+    // The other state is dropped on both sides.
+    let differences = get_differences_with_options(open, closed, &options);
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].to_string(),
+               "/html[0]/body[0]/div[0] => [Unexpected element]: element <button> present in \
+                right but missing in left");
+}
+
+#[test]
+fn test_progress() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let items = (0..50).map(|i| format!("<li>{}</li>", i)).collect::<String>();
+    let content1 = format!("<ul>{}</ul><p>a</p>", items);
+    let content2 = format!("<ul>{}</ul><p>b</p>", items);
+    let calls = Rc::new(RefCell::new(Vec::new()));
+    let recorded = calls.clone();
+    let options = DiffOptions::new().progress(10, move |visited| {
+        recorded.borrow_mut().push(visited)
+    });
+    assert_eq!(get_differences_with_options(&content1, &content2, &options),
+               get_differences(&content1, &content2));
+    let calls = calls.borrow();
+    // 106 nodes: `html`, `head`, `body`, `ul`, the `li` with their texts, `p` and its text.
+    assert_eq!(*calls, (1..11).map(|i| i * 10).collect::<Vec<_>>());
+    assert_eq!(count_compared_nodes(&content1, &DiffOptions::new()), 106);
+
+    // The subtrees below a difference aren't visited.
+    let count = Rc::new(RefCell::new(0));
+    let counter = count.clone();
+    let options = DiffOptions::new().progress(1, move |visited| *counter.borrow_mut() = visited);
+    get_differences_with_options(&content1, "<div></div>", &options);
+    // `html`, `head`, `body` and `ul` against `div`, then `p` against nothing.
+    assert_eq!(*count.borrow(), 5);
+
+    // The nodes are counted from where the comparison starts.
+    let count = Rc::new(RefCell::new(0));
+    let counter = count.clone();
+    let options = DiffOptions::new().xml_syntax(true)
+                                    .progress(1, move |visited| *counter.borrow_mut() = visited);
+    let content = "<item/><item>b</item>";
+    assert!(get_differences_with_options(content, content, &options).is_empty());
+    // The two `item` and the text.
+    assert_eq!(*count.borrow(), 3);
+    assert_eq!(count_compared_nodes(content, &options), 3);
+    let options = DiffOptions::new().auto_unwrap(true);
+    assert_eq!(count_compared_nodes("<p>a</p>", &options), 2);
+}
+
+#[test]
+fn test_collapse_nested_inline() {
+    let options = DiffOptions::new().collapse_nested_inline(NESTED_INLINE_TAGS);
+    assert_eq!(get_differences("<p><b><b>a</b></b></p>", "<p><b>a</b></p>").len(), 1);
+    assert!(get_differences_with_options("<p><b><b>a</b></b> and <em><em><em>b</em></em></em></p>",
+                                         "<p><b>a</b> and <em>b</em></p>",
+                                         &options).is_empty());
+    assert!(get_differences_with_options("<span class=\"x\"><span class=\"x\">a</span></span>",
+                                         "<span class=\"x\">a</span>", &options).is_empty());
+    // The differences below are still found.
+    let differences = get_differences_with_options("<p><em><em>a</em></em></p>",
+                                                   "<p><em>b</em></p>", &options);
+    assert_eq!(differences.len(), 1);
+    assert_eq!(differences[0].path(), "/html[0]/body[0]/p[0]/em[0]");
+    // The nesting is kept when the attributes differ, when there are other children and for the
+    // other tags.
+    assert!(!get_differences_with_options("<span class=\"x\"><span>a</span></span>",
+                                          "<span class=\"x\">a</span>", &options).is_empty());
+    assert!(!get_differences_with_options("<b><b>a</b>c</b>", "<b>ac</b>", &options).is_empty());
+    assert!(!get_differences_with_options("<small><small>a</small></small>",
+                                          "<small>a</small>", &options).is_empty());
+    // Unless they are given.
+    let options = DiffOptions::new().collapse_nested_inline(vec!["SMALL"]);
+    assert!(get_differences_with_options("<small><small>a</small></small>",
+                                         "<small>a</small>", &options).is_empty());
+    assert!(!get_differences_with_options("<b><b>a</b></b>", "<b>a</b>", &options).is_empty());
+}
+
+#[test]
+fn test_render_with_location() {
+    let options = DiffOptions::new();
+    assert_eq!(render_with_location("<p>a</p><ul></ul>", "<p>b</p>", &options, "out/index.html"),
+               "out/index.html:1:1: /html[0]/body[0]/p[0] => [Texts differ]: expected \"a\", \
+                found \"b\"\n\
+                out/index.html:1:1: /html[0]/body[0] => [One element is missing]: element <ul> \
+                present in left but missing in right\n");
+    // The texts are located at their element and the missing elements at their parent.
+    let content2 = "<!DOCTYPE html>\n<body class=\"x\">\n<div>\n <p>é <b>b</b></p></div>\n\
+                    <table><tr><td>a</td></tr></table>";
+    let located = render_with_location("<!DOCTYPE html><body class=\"x\"><div><p>é <b>c</b></p>\
+                                        <p>d</p></div><table><tr><td>b</td></tr></table>",
+                                       content2, &options, "a.html");
+    let locations = located.lines()
+                           .map(|line| line.split(':').skip(1).take(2).collect::<Vec<_>>())
+                           .map(|location| location.join(":"))
+                           .collect::<Vec<_>>();
+    assert_eq!(locations, ["4:7", "3:1", "5:12"]);
+    // With the content parsed in an `<svg>`.
+    let options = DiffOptions::new().xml_syntax(true);
+    assert!(render_with_location("<a/>\n<a/>", "<a/>\n<c/>", &options, "a.svg")
+                .starts_with("a.svg:2:1: "));
+    assert_eq!(render_with_location("<p>a</p>", "<p>a</p>", &options, "index.html"), "");
+}
+
+#[test]
+fn test_missing_subtree_descendants() {
+    let rows = (0..20).map(|i| format!("<tr class=\"r{}\"><td>{}</td><td><a href=\"/{}\">x</a>\
+                                        </td></tr>", i, i, i))
+                      .collect::<String>();
+    let content1 = format!("<h1>Title</h1><table>{}</table>", rows);
+    let differences = get_differences(&content1, "<h1>Title</h1>");
+    assert_eq!(differences.len(), 1);
+    match differences[0] {
+        Difference::NotPresent { elem: Some(ref elem), opposite_elem: None } => {
+            assert_eq!(elem.element_name, "table");
+            assert_eq!(elem.path, "/html[0]/body[0]");
+        }
+        ref d => panic!("unexpected difference: {:?}", d),
+    }
+    // The same in the other direction and when the children are compared as a set.
+    let options = DiffOptions::new().unordered(true);
+    let differences = get_differences_with_options("<h1>Title</h1>", &content1, &options);
+    assert_eq!(differences.len(), 1);
+    assert!(differences[0].is_not_present());
 }
@@ -1,43 +1,238 @@
 extern crate html_diff;
 
+use html_diff::{DiffError, DiffResult, DifferenceKind, Verbosity};
+
 use std::env;
 use std::fs::File;
-use std::io::{self, Read};
+use std::io::{self, Read, Write};
 use std::path::Path;
+use std::process;
+
+/// Exit code used when at least one pair of files differs.
+const EXIT_DIFFERENCES: i32 = 1;
+/// Exit code used when the arguments or the files couldn't be processed.
+const EXIT_ERROR: i32 = 2;
 
-fn get_file_content<P: AsRef<Path>>(p: &P) -> io::Result<String> {
+fn get_file_content<P: AsRef<Path>>(p: &P) -> Result<String, DiffError> {
     let mut f = File::open(p)?;
-    let mut buffer = String::with_capacity(1000);
-    f.read_to_string(&mut buffer)?;
-    Ok(buffer)
+    let mut buffer = Vec::with_capacity(1000);
+    f.read_to_end(&mut buffer)?;
+    Ok(String::from_utf8(buffer)?)
+}
+
+// Reads the two documents given on the standard input, separated by `delimiter` (a trailing
+// delimiter is allowed).
+fn get_stdin_contents(delimiter: &str) -> Result<(String, String), String> {
+    let mut buffer = Vec::new();
+    io::stdin().read_to_end(&mut buffer).map_err(|e| DiffError::from(e).to_string())?;
+    let content = String::from_utf8(buffer).map_err(|e| DiffError::from(e).to_string())?;
+    let mut parts = content.split(delimiter).collect::<Vec<_>>();
+    if parts.len() == 3 && parts[2].is_empty() {
+        parts.pop();
+    }
+    if parts.len() != 2 {
+        return Err(format!("expected two documents separated by {:?}, found {}",
+                           delimiter, parts.len()))
+    }
+    Ok((parts[0].to_owned(), parts[1].to_owned()))
 }
 
-fn print_error(arg: &str, v: io::Result<String>) {
+fn print_error(arg: &str, v: Result<String, DiffError>) {
     if let Err(err) = v {
         println!("\"{}\": error: {}", arg, err);
     }
 }
 
+fn kind_header(kind: DifferenceKind) -> &'static str {
+    match kind {
+        DifferenceKind::NodeType => "types",
+        DifferenceKind::NodeName => "tags",
+        DifferenceKind::NodeAttributes => "attributes",
+        DifferenceKind::NodeText => "texts",
+        DifferenceKind::NotPresent => "missing",
+        DifferenceKind::SubtreeDiffers => "subtrees",
+        DifferenceKind::AttributeOrder => "order",
+    }
+}
+
+fn write_summary(out: &mut String, names: &[String], results: &[DiffResult]) {
+    let width = names.iter().map(|n| n.len()).chain(Some("pair".len())).max().unwrap_or(0);
+    let mut header = format!("{:<width$}  differences", "pair", width = width);
+    for kind in &DifferenceKind::all() {
+        header.push_str(&format!("  {}", kind_header(*kind)));
+    }
+    out.push_str(&format!("{}  result\n", header));
+    for result in results {
+        let mut line = format!("{:<width$}  {:<11}", names[result.index],
+                               result.differences.len(), width = width);
+        for kind in &DifferenceKind::all() {
+            line.push_str(&format!("  {:<width$}", result.count(*kind),
+                                   width = kind_header(*kind).len()));
+        }
+        out.push_str(&format!("{}  {}\n", line, if result.is_equal() { "ok" } else { "FAIL" }));
+    }
+}
+
+fn parse_format(format: &str) -> Option<Verbosity> {
+    match format {
+        "terse" => Some(Verbosity::Terse),
+        "normal" => Some(Verbosity::Normal),
+        "verbose" => Some(Verbosity::Verbose),
+        _ => None,
+    }
+}
+
+fn parse_kind(kind: &str) -> Option<DifferenceKind> {
+    match kind {
+        "node_type" => Some(DifferenceKind::NodeType),
+        "node_name" => Some(DifferenceKind::NodeName),
+        "node_attributes" => Some(DifferenceKind::NodeAttributes),
+        "node_text" => Some(DifferenceKind::NodeText),
+        "not_present" => Some(DifferenceKind::NotPresent),
+        "subtree_differs" => Some(DifferenceKind::SubtreeDiffers),
+        "attribute_order" => Some(DifferenceKind::AttributeOrder),
+        _ => None,
+    }
+}
+
+struct Args {
+    verbosity: Verbosity,
+    output: Option<String>,
+    stdin: bool,
+    delimiter: String,
+    // The kinds of differences to report, all of them if `None`.
+    only: Option<Vec<DifferenceKind>>,
+    summary: bool,
+    files: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        verbosity: Verbosity::Normal,
+        output: None,
+        stdin: false,
+        delimiter: "\0".to_owned(),
+        only: None,
+        summary: false,
+        files: Vec::new(),
+    };
+    let mut it = env::args().skip(1);
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "-q" => args.verbosity = Verbosity::Terse,
+            "-v" => args.verbosity = Verbosity::Verbose,
+            "--format" => {
+                let format = it.next().ok_or("Missing value for \"--format\"")?;
+                args.verbosity = parse_format(&format).ok_or_else(|| {
+                    format!("Unknown format \"{}\", expected \"terse\", \"normal\" or \
+                             \"verbose\"", format)
+                })?;
+            }
+            "--output" => args.output = Some(it.next().ok_or("Missing value for \"--output\"")?),
+            "--stdin" => args.stdin = true,
+            "--delimiter" => {
+                args.delimiter = it.next().ok_or("Missing value for \"--delimiter\"")?;
+                if args.delimiter.is_empty() {
+                    return Err("The delimiter can't be empty".to_owned())
+                }
+            }
+            "--only" => {
+                let kinds = it.next().ok_or("Missing value for \"--only\"")?;
+                args.only = Some(kinds.split(',').map(|kind| {
+                    parse_kind(kind.trim()).ok_or_else(|| {
+                        format!("Unknown kind of difference \"{}\", expected \"node_type\", \
+                                 \"node_name\", \"node_attributes\", \"node_text\", \
+                                 \"not_present\", \"subtree_differs\" or \"attribute_order\"",
+                                kind)
+                    })
+                }).collect::<Result<_, _>>()?);
+            }
+            "--summary" => args.summary = true,
+            _ => args.files.push(arg),
+        }
+    }
+    if args.files.len() & 1 != 0 {
+        return Err("Need to pass an even number of HTML files".to_owned())
+    }
+    Ok(args)
+}
+
 fn main() {
-    let args: Vec<String> = env::args().skip(1).collect();
-    if args.len() & 1 != 0 {
-        println!("Need to pass an even number of HTML files");
-        return
-    }
-    for args in args.chunks(2) {
-        let arg1 = &args[0];
-        let arg2 = &args[1];
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(err) => {
+            println!("{}", err);
+            process::exit(EXIT_ERROR);
+        }
+    };
+    let mut names = Vec::new();
+    let mut pairs = Vec::new();
+    let mut has_errors = false;
+    if args.stdin {
+        match get_stdin_contents(&args.delimiter) {
+            Ok(contents) => {
+                names.push("<stdin 1> / <stdin 2>".to_owned());
+                pairs.push(contents);
+            }
+            Err(err) => {
+                println!("<stdin>: error: {}", err);
+                has_errors = true;
+            }
+        }
+    }
+    for files in args.files.chunks(2) {
+        let arg1 = &files[0];
+        let arg2 = &files[1];
         match (get_file_content(&arg1), get_file_content(&arg2)) {
             (Ok(content1), Ok(content2)) => {
-                let differences = html_diff::get_differences(&content1, &content2);
-                for diff in differences {
-                    println!("=> {}", diff.to_string());
-                }
+                names.push(format!("{} / {}", arg1, arg2));
+                pairs.push((content1, content2));
             }
             (x, y) => {
                 print_error(arg1, x);
                 print_error(arg2, y);
+                has_errors = true;
+            }
+        }
+    }
+    let mut results = html_diff::diff_many(&pairs);
+    if let Some(ref only) = args.only {
+        for result in &mut results {
+            result.differences.retain(|diff| only.contains(&diff.kind()));
+        }
+    }
+    let mut report = String::new();
+    for result in results.iter().filter(|_| !args.summary) {
+        if !result.is_equal() {
+            report.push_str(&format!("{}:\n", names[result.index]));
+        }
+        for warning in &result.warnings {
+            report.push_str(&format!("warning: {}\n", warning));
+        }
+        for diff in &result.differences {
+            report.push_str(&format!("=> {}\n", diff.render(args.verbosity)));
+        }
+    }
+    if !results.is_empty() {
+        write_summary(&mut report, &names, &results);
+    }
+    match args.output {
+        Some(ref output) => {
+            let written = File::create(output).and_then(|mut f| f.write_all(report.as_bytes()));
+            if let Err(err) = written {
+                println!("\"{}\": error: {}", output, err);
+                process::exit(EXIT_ERROR);
             }
+            println!("{} difference(s) found in {} pair(s) of files, report written to \"{}\"",
+                     results.iter().map(|r| r.differences.len()).sum::<usize>(),
+                     results.len(),
+                     output);
         }
+        None => print!("{}", report),
+    }
+    if has_errors {
+        process::exit(EXIT_ERROR);
+    } else if results.iter().any(|r| !r.is_equal()) {
+        process::exit(EXIT_DIFFERENCES);
     }
 }
@@ -0,0 +1,1218 @@
+use kuchiki::NodeRef;
+use {is_tracking_element, DifferenceKind};
+
+use std::cell::RefCell;
+use std::cmp;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+type MatchCallback = dyn FnMut(&NodeRef, &NodeRef, &str);
+
+/// The inline formatting elements which render the same when they are nested in an identical
+/// one, unlike `<small>` or `<sub>` whose effects add up (see
+/// `DiffOptions::collapse_nested_inline`).
+pub const NESTED_INLINE_TAGS: &[&str] = &["b", "strong", "i", "em", "u", "s", "code", "span"];
+
+/// The callback given to `DiffOptions::on_match`.
+#[derive(Clone)]
+pub(crate) struct MatchHook(Rc<RefCell<MatchCallback>>);
+
+impl MatchHook {
+    pub(crate) fn call(&self, node1: &NodeRef, node2: &NodeRef, path: &[String]) {
+        (*self.0.borrow_mut())(node1, node2, &path.join("/"))
+    }
+}
+
+impl fmt::Debug for MatchHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MatchHook")
+    }
+}
+
+/// The callback given to `DiffOptions::progress`, called every `every` visited nodes.
+#[derive(Clone)]
+pub(crate) struct ProgressHook {
+    pub(crate) every: usize,
+    f: Rc<RefCell<dyn FnMut(usize)>>,
+}
+
+impl ProgressHook {
+    pub(crate) fn call(&self, visited: usize) {
+        (*self.f.borrow_mut())(visited)
+    }
+}
+
+impl fmt::Debug for ProgressHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ProgressHook")
+    }
+}
+
+/// The callback given to `DiffOptions::path_rewriter`.
+#[derive(Clone)]
+pub(crate) struct PathRewriter(Rc<dyn Fn(&str) -> String>);
+
+impl PathRewriter {
+    pub(crate) fn call(&self, path: &str) -> String {
+        (self.0)(path)
+    }
+}
+
+impl fmt::Debug for PathRewriter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PathRewriter")
+    }
+}
+
+/// The predicate given to `DiffOptions::ignore_elements`.
+#[derive(Clone)]
+pub(crate) struct ElementFilter(Rc<dyn Fn(&NodeRef) -> bool>);
+
+impl ElementFilter {
+    pub(crate) fn call(&self, node: &NodeRef) -> bool {
+        (self.0)(node)
+    }
+}
+
+impl fmt::Debug for ElementFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ElementFilter")
+    }
+}
+
+// The prefixes of the attributes added by email clients (the dark mode colors of Outlook, the
+// links added by Apple Mail and the redirections of Gmail).
+const EMAIL_CLIENT_ATTRIBUTE_PREFIXES: &[&str] = &["data-ogs", "data-oga",
+                                                   "x-apple-data-detectors",
+                                                   "data-saferedirecturl"];
+
+/// Allows to configure how two HTML contents are compared.
+///
+/// ```
+/// use html_diff::{get_differences_with_options, DiffOptions};
+///
+/// let options = DiffOptions::new().collapse_text_nodes(true);
+/// let differences = get_differences_with_options("<p>a&amp;b</p>", "<p>a&b</p>", &options);
+/// assert!(differences.is_empty());
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    pub(crate) collapse_text_nodes: bool,
+    pub(crate) decode_percent_encoding: Vec<String>,
+    pub(crate) ignored_attribute_prefixes: Vec<String>,
+    pub(crate) normalize_whitespace: bool,
+    pub(crate) case_insensitive_text: bool,
+    pub(crate) max_depth: Option<usize>,
+    pub(crate) mark_subtrees_below_max_depth: bool,
+    pub(crate) max_differences: Option<usize>,
+    pub(crate) unwrap_single_child_tags: Vec<String>,
+    pub(crate) similarity_weights: HashMap<DifferenceKind, f64>,
+    pub(crate) form_semantics: bool,
+    pub(crate) only_attribute_prefixes: Vec<String>,
+    pub(crate) ignore_text: bool,
+    pub(crate) resolve_svg_use: bool,
+    pub(crate) min_text_len: usize,
+    pub(crate) unordered: bool,
+    pub(crate) only_compare_attributes: Vec<String>,
+    pub(crate) normalize_tables: bool,
+    pub(crate) on_match: Option<MatchHook>,
+    pub(crate) url_bases: Option<(String, String)>,
+    pub(crate) max_content_len: Option<usize>,
+    pub(crate) stripped_attributes: Vec<String>,
+    pub(crate) stripped_prefixes: Vec<String>,
+    pub(crate) stripped_suffixes: Vec<String>,
+    pub(crate) head_meta_link_unordered: bool,
+    pub(crate) auto_unwrap: bool,
+    pub(crate) allow_attribute_additions: bool,
+    pub(crate) equivalent_tags: Vec<(String, String)>,
+    pub(crate) normalize_dom: bool,
+    pub(crate) ignore_removals: bool,
+    pub(crate) treat_nbsp_as_space: bool,
+    pub(crate) json_attributes: Vec<String>,
+    pub(crate) transparent_inline_tags: Vec<String>,
+    pub(crate) ignore_hidden: bool,
+    pub(crate) compare_effective_lang: bool,
+    pub(crate) trim_attribute_values: bool,
+    pub(crate) preserve_pre_whitespace: bool,
+    pub(crate) path_rewriter: Option<PathRewriter>,
+    pub(crate) style_as_declarations: bool,
+    pub(crate) class_as_set: bool,
+    pub(crate) numeric_tolerance: f64,
+    pub(crate) numeric_list_attributes: Vec<String>,
+    pub(crate) trim_path_prefix: Vec<String>,
+    pub(crate) min_subtree_size: usize,
+    pub(crate) strict_attribute_order: bool,
+    pub(crate) ignore_elements: Option<ElementFilter>,
+    pub(crate) options_as_set: bool,
+    pub(crate) opaque_text_over: Option<usize>,
+    pub(crate) xml_syntax: bool,
+    pub(crate) case_sensitive_names: bool,
+    pub(crate) elements_only: bool,
+    pub(crate) br_as_newline: bool,
+    pub(crate) selected_state: Option<(String, String)>,
+    pub(crate) progress: Option<ProgressHook>,
+    pub(crate) collapse_nested_inline: Vec<String>,
+}
+
+impl Default for DiffOptions {
+    fn default() -> DiffOptions {
+        DiffOptions {
+            collapse_text_nodes: true,
+            decode_percent_encoding: Vec::new(),
+            ignored_attribute_prefixes: Vec::new(),
+            normalize_whitespace: false,
+            case_insensitive_text: false,
+            max_depth: None,
+            mark_subtrees_below_max_depth: false,
+            max_differences: None,
+            unwrap_single_child_tags: Vec::new(),
+            similarity_weights: HashMap::new(),
+            form_semantics: false,
+            only_attribute_prefixes: Vec::new(),
+            ignore_text: false,
+            resolve_svg_use: false,
+            min_text_len: 0,
+            unordered: false,
+            only_compare_attributes: Vec::new(),
+            normalize_tables: false,
+            on_match: None,
+            url_bases: None,
+            max_content_len: None,
+            stripped_attributes: Vec::new(),
+            stripped_prefixes: Vec::new(),
+            stripped_suffixes: Vec::new(),
+            head_meta_link_unordered: false,
+            auto_unwrap: false,
+            allow_attribute_additions: false,
+            equivalent_tags: Vec::new(),
+            normalize_dom: false,
+            ignore_removals: false,
+            treat_nbsp_as_space: false,
+            json_attributes: Vec::new(),
+            transparent_inline_tags: Vec::new(),
+            ignore_hidden: false,
+            compare_effective_lang: false,
+            trim_attribute_values: false,
+            preserve_pre_whitespace: false,
+            path_rewriter: None,
+            style_as_declarations: false,
+            class_as_set: false,
+            numeric_tolerance: 0.,
+            numeric_list_attributes: Vec::new(),
+            trim_path_prefix: Vec::new(),
+            min_subtree_size: 0,
+            strict_attribute_order: false,
+            ignore_elements: None,
+            options_as_set: false,
+            opaque_text_over: None,
+            xml_syntax: false,
+            case_sensitive_names: false,
+            elements_only: false,
+            br_as_newline: false,
+            selected_state: None,
+            progress: None,
+            collapse_nested_inline: Vec::new(),
+        }
+    }
+}
+
+impl DiffOptions {
+    /// Returns the default options.
+    pub fn new() -> DiffOptions {
+        DiffOptions::default()
+    }
+
+    /// Returns options for accessibility regression checks: only the `role` and `aria-*`
+    /// attributes are compared and texts are ignored.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::accessibility();
+    /// assert!(get_differences_with_options("<b class=\"a\">x</b>", "<b class=\"b\">y</b>",
+    ///                                      &options).is_empty());
+    /// assert_eq!(get_differences_with_options("<b role=\"tab\"></b>", "<b role=\"tablist\"></b>",
+    ///                                         &options).len(), 1);
+    /// ```
+    pub fn accessibility() -> DiffOptions {
+        DiffOptions::new().only_compare_attributes(["role"])
+                          .only_attribute_prefixes(["aria-"])
+                          .ignore_text(true)
+    }
+
+    /// Returns options ignoring the formatting of the documents: the texts are compared with
+    /// their whitespace collapsed (`normalize_whitespace(true)`) except inside `<pre>` and
+    /// `<textarea>` (`preserve_pre_whitespace(true)`), and the attribute values without their
+    /// leading and trailing whitespace (`trim_attribute_values(true)`).
+    ///
+    /// The whitespace inside the attribute values, and the whitespace-only texts inside `<pre>`
+    /// (which are always ignored, like everywhere else) aren't normalized.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::ignore_insignificant_whitespace();
+    /// assert!(get_differences_with_options("<p class=\"a \">Some\n  text</p>",
+    ///                                      "<p class=\"a\"> Some text </p>",
+    ///                                      &options).is_empty());
+    /// assert_eq!(get_differences_with_options("<pre>a  b</pre>", "<pre>a b</pre>",
+    ///                                         &options).len(), 1);
+    /// ```
+    pub fn ignore_insignificant_whitespace() -> DiffOptions {
+        DiffOptions::new().normalize_whitespace(true)
+                          .preserve_pre_whitespace(true)
+                          .trim_attribute_values(true)
+    }
+
+    /// Returns options for HTML email templates, whose styles are inlined by tools which don't
+    /// keep their order: the `style` attributes are compared as sets of declarations
+    /// (`style_as_declarations(true)`), the `class` attributes as sets of classes
+    /// (`class_as_set(true)`), the attribute values without their leading and trailing
+    /// whitespace (`trim_attribute_values(true)`), and the attributes added by email clients
+    /// (`data-ogsc` and the other Outlook dark mode attributes, `x-apple-data-detectors*` and
+    /// `data-saferedirecturl`) are ignored.
+    ///
+    /// The texts are compared as they are, `normalize_whitespace` can be enabled on top of it.
+    pub fn email() -> DiffOptions {
+        DiffOptions::new().style_as_declarations(true)
+                          .class_as_set(true)
+                          .trim_attribute_values(true)
+                          .ignore_attribute_prefixes(EMAIL_CLIENT_ATTRIBUTE_PREFIXES)
+    }
+
+    /// Returns options comparing a minified document with its source: on top of
+    /// `ignore_insignificant_whitespace()`, the `class` attributes are compared as sets of
+    /// classes (`class_as_set(true)`), the `style` attributes as sets of declarations
+    /// (`style_as_declarations(true)`) and the form controls by their state
+    /// (`form_semantics(true)`, so `disabled="disabled"` and `disabled` match).
+    ///
+    /// The omitted optional tags (`</p>`, `</li>`, `<head>`...) are put back by the parser, the
+    /// removed quotes and comments and the order of the attributes never matter, so they don't
+    /// need any option.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::minification_tolerant();
+    /// assert!(get_differences_with_options("<ul>\n  <li class=\"a  b\">One</li>\n</ul>",
+    ///                                      "<ul><li class=\"a b\">One</ul>",
+    ///                                      &options).is_empty());
+    /// ```
+    pub fn minification_tolerant() -> DiffOptions {
+        DiffOptions::ignore_insignificant_whitespace().class_as_set(true)
+                                                      .style_as_declarations(true)
+                                                      .form_semantics(true)
+    }
+
+    /// Returns options dropping the analytics tracking elements, which are often injected
+    /// differently on each page load, from both trees (see `is_tracking_element` for the
+    /// heuristics: 1x1 images, and the beacons of the common analytics and advertising
+    /// services).
+    ///
+    /// The heuristics can be extended with `ignore_elements`, which replaces them:
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, is_tracking_element, DiffOptions, NodeRef};
+    ///
+    /// let options = DiffOptions::ignore_tracking();
+    /// assert!(get_differences_with_options(
+    ///     "<p>a</p><img src=\"https://www.facebook.com/tr?id=1\">",
+    ///     "<p>a</p><img src=\"/pixel.gif?r=2\" width=\"1\" height=\"1\">",
+    ///     &options).is_empty());
+    ///
+    /// let options = DiffOptions::new().ignore_elements(|node: &NodeRef| {
+    ///     is_tracking_element(node) ||
+    ///     node.as_element().map(|e| e.attributes.borrow().get("src") == Some("/stats.js"))
+    ///                      .unwrap_or(false)
+    /// });
+    /// assert!(get_differences_with_options("<p>a</p><script src=\"/stats.js\"></script>",
+    ///                                      "<p>a</p>", &options).is_empty());
+    /// ```
+    pub fn ignore_tracking() -> DiffOptions {
+        DiffOptions::new().ignore_elements(is_tracking_element)
+    }
+
+    /// Returns options for the XML-based formats (RSS, Atom, XHTML), which are case-sensitive
+    /// and order-sensitive: the contents are parsed with `xml_syntax(true)` and the names are
+    /// compared with `case_sensitive_names(true)`.
+    ///
+    /// The HTML behaviors it turns off are:
+    ///
+    /// * the case-insensitive tag and attribute names,
+    /// * the void elements (`<link>`, `<meta>`...) and the ignored self-closing flags,
+    /// * the CDATA sections parsed as comments (they are texts),
+    /// * the implied `<html>`, `<head>` and `<body>` elements.
+    ///
+    /// The other HTML normalizations are off by default and stay so: the boolean attributes
+    /// aren't normalized (`form_semantics`), the children are compared in order (`unordered`,
+    /// `head_meta_link_unordered`, `options_as_set`), and neither the texts
+    /// (`case_insensitive_text`, `normalize_whitespace`) nor the tags (`equivalent_tags`,
+    /// `normalize_dom`) are rewritten. See `xml_syntax` for the cases which are still parsed
+    /// as HTML.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::xml_strict();
+    /// let differences = get_differences_with_options("<item><guid/><pubDate>1</pubDate></item>",
+    ///                                                "<item><guid/><pubdate>1</pubdate></item>",
+    ///                                                &options);
+    /// assert_eq!(differences.len(), 1);
+    /// assert_eq!(differences[0].path(), "/item[0]");
+    /// ```
+    pub fn xml_strict() -> DiffOptions {
+        DiffOptions::new().xml_syntax(true).case_sensitive_names(true)
+    }
+
+    /// Returns options for layout regression checks, comparing the structural skeleton of the
+    /// documents: the texts are ignored (`ignore_text(true)`) and only the given attributes are
+    /// compared (`only_compare_attributes(attributes)`). Element names, the node types and the
+    /// number of children are still compared.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::skeleton(["class"]);
+    /// assert!(get_differences_with_options("<p class=\"a\" id=\"x\">text</p>",
+    ///                                      "<p class=\"a\">other text</p>",
+    ///                                      &options).is_empty());
+    /// ```
+    pub fn skeleton<I, S>(attributes: I) -> DiffOptions
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        DiffOptions::new().only_compare_attributes(attributes).ignore_text(true)
+    }
+
+    /// Merges adjacent text nodes (once comments have been removed) before comparing them.
+    ///
+    /// Enabled by default.
+    pub fn collapse_text_nodes(mut self, value: bool) -> DiffOptions {
+        self.collapse_text_nodes = value;
+        self
+    }
+
+    /// Compares the percent-decoded values of the given attributes, so `href="a%20b"` and
+    /// `href="a b"` are considered equal. If a value contains an invalid percent sequence, the raw
+    /// values are compared.
+    pub fn decode_percent_encoding<I, S>(mut self, attributes: I) -> DiffOptions
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        self.decode_percent_encoding = attributes.into_iter()
+                                                 .map(|s| s.as_ref().to_owned())
+                                                 .collect();
+        self
+    }
+
+    /// Ignores the attributes whose name starts with one of the given prefixes (like `data-v-`
+    /// or `_ngcontent-`), on both sides.
+    pub fn ignore_attribute_prefixes<I, S>(mut self, prefixes: I) -> DiffOptions
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        self.ignored_attribute_prefixes = prefixes.into_iter()
+                                                  .map(|s| s.as_ref().to_owned())
+                                                  .collect();
+        self
+    }
+
+    /// Collapses every sequence of whitespace characters into one space and removes leading and
+    /// trailing whitespace before comparing texts.
+    pub fn normalize_whitespace(mut self, value: bool) -> DiffOptions {
+        self.normalize_whitespace = value;
+        self
+    }
+
+    /// Compares texts case-insensitively (`<p>Submit</p>` and `<p>submit</p>` are equal). The
+    /// reported texts keep their original case.
+    ///
+    /// It is applied after the whitespace normalization if both are enabled.
+    pub fn case_insensitive_text(mut self, value: bool) -> DiffOptions {
+        self.case_insensitive_text = value;
+        self
+    }
+
+    /// Doesn't go through the children of the nodes at the given depth, so no difference deeper
+    /// than `max_depth` is reported (see `ElementInformation::depth`).
+    ///
+    /// Reported differences still count toward `max_differences`, including the markers added
+    /// by `mark_subtrees_below_max_depth`.
+    pub fn max_depth(mut self, max_depth: usize) -> DiffOptions {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// When `max_depth` is reached, reports a `Difference::SubtreeDiffers` for each pair of
+    /// subtrees containing differences instead of ignoring them.
+    ///
+    /// The subtrees have to be fully compared to count their differences, so this cancels out
+    /// the work saved by `max_depth` (but not the output).
+    pub fn mark_subtrees_below_max_depth(mut self, value: bool) -> DiffOptions {
+        self.mark_subtrees_below_max_depth = value;
+        self
+    }
+
+    /// Stops the comparison once `max_differences` differences have been found. With `0`, no
+    /// difference is reported.
+    pub fn max_differences(mut self, max_differences: usize) -> DiffOptions {
+        self.max_differences = Some(max_differences);
+        self
+    }
+
+    /// Treats the given tags as transparent when they only wrap one element: if one side has
+    /// `<div><p>a</p></div>` where the other side has `<p>a</p>`, the two `p` elements are
+    /// compared as if the `div` wasn't there.
+    ///
+    /// A wrapper is only skipped if the element on the other side has a different name than the
+    /// wrapper and the same name as its only child (text and comments around the child have to
+    /// be empty), so changes in the wrapped content are still reported.
+    pub fn unwrap_single_child_tags<I, S>(mut self, tags: I) -> DiffOptions
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        self.unwrap_single_child_tags = tags.into_iter().map(|s| s.as_ref().to_owned()).collect();
+        self
+    }
+
+    pub(crate) fn similarity_weight(&self, kind: DifferenceKind) -> f64 {
+        self.similarity_weights.get(&kind).cloned().unwrap_or(1.)
+    }
+
+    /// Sets the weight of each kind of difference in `similarity_score`. The kinds which aren't
+    /// in `weights` have a weight of `1.`, which is the default for all kinds.
+    ///
+    /// Only the relative weights matter: a kind with a weight of `0.5` counts half as much as a
+    /// kind with a weight of `1.` (the highest weight by default).
+    pub fn similarity_weights(mut self, weights: HashMap<DifferenceKind, f64>) -> DiffOptions {
+        self.similarity_weights = weights;
+        self
+    }
+
+    /// Compares form controls by the state they represent rather than by their markup:
+    /// `checked="checked"` and `checked` are equal, `<input>` is an `<input type="text">`,
+    /// `<option>a</option>` has `a` as value and, in a `<select>` without `multiple`, only the
+    /// option displayed as selected (the last one with `selected` or the first enabled one) is
+    /// considered `selected`.
+    pub fn form_semantics(mut self, value: bool) -> DiffOptions {
+        self.form_semantics = value;
+        self
+    }
+
+    /// Only compares the attributes whose name starts with one of the given prefixes, the other
+    /// ones are ignored on both sides (see also `only_compare_attributes`).
+    /// `ignore_attribute_prefixes` still applies to the attributes matching these prefixes.
+    ///
+    /// An empty list (the default) compares all attributes.
+    pub fn only_attribute_prefixes<I, S>(mut self, prefixes: I) -> DiffOptions
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        self.only_attribute_prefixes = prefixes.into_iter()
+                                               .map(|s| s.as_ref().to_owned())
+                                               .collect();
+        self
+    }
+
+    /// Considers all texts equal, so only the structure and the attributes are compared.
+    pub fn ignore_text(mut self, value: bool) -> DiffOptions {
+        self.ignore_text = value;
+        self
+    }
+
+    /// Compares the elements referenced by SVG `<use href="#id">` elements as if they were
+    /// their children, so a change in a `<symbol>` is also reported where it is used. The
+    /// references which don't resolve in the document are compared as missing elements.
+    pub fn resolve_svg_use(mut self, value: bool) -> DiffOptions {
+        self.resolve_svg_use = value;
+        self
+    }
+
+    /// Ignores the text differences where both texts are shorter than `min_text_len`
+    /// characters (`<p>.</p>` and `<p>!</p>` with a minimum of `2` for example).
+    ///
+    /// The length is counted after the whitespace normalization and the case folding, if
+    /// enabled.
+    pub fn min_text_len(mut self, min_text_len: usize) -> DiffOptions {
+        self.min_text_len = min_text_len;
+        self
+    }
+
+    /// Matches the children of each node as a multiset instead of by position: two children
+    /// match if they have the same content, whatever the order of their own children (texts
+    /// are compared after their normalization).
+    ///
+    /// Reordering never produces a difference in this mode and all the differences are
+    /// `Difference::NotPresent`: the remaining elements with the same name and attributes on
+    /// both sides are compared the same way, in order, and all the other children are reported
+    /// as missing.
+    pub fn unordered(mut self, value: bool) -> DiffOptions {
+        self.unordered = value;
+        self
+    }
+
+    /// Only compares the given attributes, the other ones are ignored on both sides.
+    ///
+    /// It can be combined with `only_attribute_prefixes`: an attribute is then compared if it
+    /// is in this list or matches one of the prefixes. An empty list (the default) compares
+    /// all attributes.
+    pub fn only_compare_attributes<I, S>(mut self, attributes: I) -> DiffOptions
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        self.only_compare_attributes = attributes.into_iter()
+                                                 .map(|s| s.as_ref().to_owned())
+                                                 .collect();
+        self
+    }
+
+    /// Compares the rows of tables regardless of the `<thead>`, `<tbody>` and `<tfoot>`
+    /// sections containing them: the rows are matched in order as if they were the direct
+    /// children of the `<table>` (their paths don't contain the sections), and the cells of
+    /// each row by column position.
+    ///
+    /// The sections are also put in their canonical order: the `<caption>` first, then the
+    /// columns, the `<thead>` rows, the body rows and the `<tfoot>` rows last (wherever the
+    /// `<tfoot>` is written). The `<col>` elements are compared without their `<colgroup>`
+    /// (the ones without `<col>` are kept since their `span` matters).
+    pub fn normalize_tables(mut self, value: bool) -> DiffOptions {
+        self.normalize_tables = value;
+        self
+    }
+
+    /// Calls `f` on each pair of nodes matching each other (before going through their
+    /// children), with the path of their parent (see `ElementInformation::path`).
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    ///
+    /// let matched = Rc::new(Cell::new(0));
+    /// let counter = matched.clone();
+    /// let options = DiffOptions::new().on_match(move |_, _, _| counter.set(counter.get() + 1));
+    /// get_differences_with_options("<p>a</p><p>b</p>", "<p>a</p><p>c</p>", &options);
+    /// // `html`, `head`, `body`, the two `p` and the `a` text.
+    /// assert_eq!(matched.get(), 6);
+    /// ```
+    pub fn on_match<F>(mut self, f: F) -> DiffOptions
+        where F: FnMut(&NodeRef, &NodeRef, &str) + 'static
+    {
+        self.on_match = Some(MatchHook(Rc::new(RefCell::new(f))));
+        self
+    }
+
+    /// Resolves the URL attributes (`href`, `src`, `action`...) of the first content against
+    /// `base1` and the ones of the second content against `base2` before comparing them, so
+    /// `href="page.html"` on a page served from `https://a.com/v2/` and
+    /// `href="/v2/page.html"` are equal.
+    ///
+    /// If a base isn't an absolute URL, the values of its side are compared as they are.
+    pub fn resolve_urls_against(mut self, base1: &str, base2: &str) -> DiffOptions {
+        self.url_bases = Some((base1.to_owned(), base2.to_owned()));
+        self
+    }
+
+    /// Truncates the `element_content` and `parent_content` of the reported elements to
+    /// `max_content_len` characters (followed by `...`), to keep the reports of large documents
+    /// readable.
+    pub fn max_content_len(mut self, max_content_len: usize) -> DiffOptions {
+        self.max_content_len = Some(max_content_len);
+        self
+    }
+
+    /// Removes the first matching prefix and the first matching suffix from the values of the
+    /// given attributes before comparing them, so the asset URLs `https://cdn1.a.com/app.js?v=1`
+    /// and `https://cdn2.a.com/app.js?v=2` can be equal. A `"?"` suffix removes the query
+    /// string (and the fragment) of the value.
+    ///
+    /// The reported attributes keep their raw values.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().strip_attribute_affixes(
+    ///     ["src"], ["https://cdn1.a.com", "https://cdn2.a.com"], ["?"]);
+    /// assert!(get_differences_with_options("<script src=\"https://cdn1.a.com/app.js?v=1\">\
+    ///                                       </script>",
+    ///                                      "<script src=\"https://cdn2.a.com/app.js?v=2\">\
+    ///                                       </script>",
+    ///                                      &options).is_empty());
+    /// ```
+    pub fn strip_attribute_affixes<A, P, X, S>(mut self, attributes: A, prefixes: P,
+                                               suffixes: X) -> DiffOptions
+        where A: IntoIterator<Item = S>,
+              P: IntoIterator<Item = S>,
+              X: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        let to_strings = |values: Vec<S>| {
+            values.iter().map(|s| s.as_ref().to_owned()).collect::<Vec<_>>()
+        };
+        self.stripped_attributes = to_strings(attributes.into_iter().collect());
+        self.stripped_prefixes = to_strings(prefixes.into_iter().collect());
+        self.stripped_suffixes = to_strings(suffixes.into_iter().collect());
+        self
+    }
+
+    /// Compares the `<meta>` and `<link>` elements of `<head>` as a set, wherever they are in
+    /// `<head>`: the elements are matched by their distinguishing attributes (`name`,
+    /// `property`, `http-equiv`, `itemprop` or `charset` for `<meta>`, `rel`, `href`,
+    /// `hreflang` and `media` for `<link>`) and then compared. The other children of `<head>`
+    /// are still compared in order.
+    pub fn head_meta_link_unordered(mut self, value: bool) -> DiffOptions {
+        self.head_meta_link_unordered = value;
+        self
+    }
+
+    /// When both contents are fragments (no doctype and no explicit `<html>`, `<head>` or
+    /// `<body>` tag), compares the content of the `<body>` the parser adds around them, so the
+    /// paths start at the real content (`/ul[0]/li[0]` instead of
+    /// `/html[0]/body[0]/ul[0]/li[0]`).
+    ///
+    /// Nothing changes if one of the fragments has elements which the parser moves into
+    /// `<head>` (like `<title>`). Disabled by default since it changes the reported paths.
+    pub fn auto_unwrap(mut self, value: bool) -> DiffOptions {
+        self.auto_unwrap = value;
+        self
+    }
+
+    /// Accepts the elements of the second content which only add attributes to the ones of the
+    /// first content: no `NodeAttributes` difference is reported as long as every attribute of
+    /// the first element is present (with a matching value) on the second one.
+    ///
+    /// This is asymmetric: an attribute added in the first content (or removed from the second
+    /// one) is still reported, so swapping the two contents gives different results.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::default().allow_attribute_additions(true);
+    /// assert!(get_differences_with_options("<p class=\"a\">x</p>",
+    ///                                      "<p class=\"a\" data-track=\"1\">x</p>",
+    ///                                      &options).is_empty());
+    /// assert_eq!(get_differences_with_options("<p class=\"a\" data-track=\"1\">x</p>",
+    ///                                         "<p class=\"a\">x</p>",
+    ///                                         &options).len(), 1);
+    /// ```
+    pub fn allow_attribute_additions(mut self, value: bool) -> DiffOptions {
+        self.allow_attribute_additions = value;
+        self
+    }
+
+    /// Considers the listed pairs of tags as having the same name, in both directions (so
+    /// `<b>` and `<strong>` don't give a `NodeName` difference with `[("b", "strong")]`). Their
+    /// attributes and children are still compared and the differences keep the actual names.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::default().equivalent_tags([("b", "strong"), ("i", "em")]);
+    /// assert!(get_differences_with_options("<p><b>a</b> <em>b</em></p>",
+    ///                                      "<p><strong>a</strong> <i>b</i></p>",
+    ///                                      &options).is_empty());
+    /// ```
+    pub fn equivalent_tags<I, S>(mut self, pairs: I) -> DiffOptions
+        where I: IntoIterator<Item = (S, S)>,
+              S: AsRef<str>
+    {
+        self.equivalent_tags = pairs.into_iter()
+                                    .map(|(a, b)| (a.as_ref().to_owned(), b.as_ref().to_owned()))
+                                    .collect();
+        self
+    }
+
+    pub(crate) fn are_equivalent_tags(&self, tag1: &str, tag2: &str) -> bool {
+        self.equivalent_tags.iter().any(|pair| {
+            (pair.0 == tag1 && pair.1 == tag2) || (pair.0 == tag2 && pair.1 == tag1)
+        })
+    }
+
+    /// Lowercases the attribute names of the HTML elements before comparing them, like
+    /// browsers do (so `dataFoo` and `datafoo` match). It only matters for the trees which
+    /// weren't built by the parser (see `get_differences_against`), since it already lowercases
+    /// them.
+    ///
+    /// The attributes of foreign content (SVG and MathML elements, like `viewBox`) keep their
+    /// case.
+    pub fn normalize_dom(mut self, value: bool) -> DiffOptions {
+        self.normalize_dom = value;
+        self
+    }
+
+    /// Doesn't report the nodes which are only in the first content (the
+    /// `Difference::NotPresent` with only `elem` set): only what the second content adds or
+    /// changes is reported.
+    ///
+    /// This is directional: the nodes only in the second content are still reported, so
+    /// swapping the two contents gives different results.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::default().ignore_removals(true);
+    /// assert!(get_differences_with_options("<p>a</p><p>b</p>", "<p>a</p>",
+    ///                                      &options).is_empty());
+    /// assert_eq!(get_differences_with_options("<p>a</p>", "<p>a</p><p>b</p>",
+    ///                                         &options).len(), 1);
+    /// ```
+    pub fn ignore_removals(mut self, value: bool) -> DiffOptions {
+        self.ignore_removals = value;
+        self
+    }
+
+    /// Compares the non-breaking spaces (`\u{a0}`, `&nbsp;`) of the texts as regular spaces.
+    /// The reported texts keep their original characters.
+    ///
+    /// `normalize_whitespace` already collapses the non-breaking spaces with the other
+    /// whitespace characters, so this is only needed without it.
+    pub fn treat_nbsp_as_space(mut self, value: bool) -> DiffOptions {
+        self.treat_nbsp_as_space = value;
+        self
+    }
+
+    /// Compares the values of the given attributes as JSON (like `data-props`): the order of the
+    /// keys of the objects doesn't matter, nor does the whitespace between the tokens. If one of
+    /// the two values isn't valid JSON, they are compared as strings.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::default().json_attributes(["data-props"]);
+    /// assert!(get_differences_with_options("<div data-props='{\"a\": 1, \"b\": [2]}'></div>",
+    ///                                      "<div data-props='{\"b\":[2],\"a\":1}'></div>",
+    ///                                      &options).is_empty());
+    /// ```
+    pub fn json_attributes<I, S>(mut self, attributes: I) -> DiffOptions
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        self.json_attributes = attributes.into_iter().map(|s| s.as_ref().to_owned()).collect();
+        self
+    }
+
+    /// Compares the elements with one of the given names which only contain text as if they
+    /// were that text, so `hello` and `<span>hello</span>` match. The text is merged with the
+    /// texts around it (unless `collapse_text_nodes` is disabled), so `Say <b>hello</b>` and
+    /// `Say hello` match with `["b"]`.
+    ///
+    /// The elements containing other elements are still compared as elements.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::default().transparent_inline_tags(["span"]);
+    /// assert!(get_differences_with_options("<p>Say <span>hello</span>!</p>", "<p>Say hello!</p>",
+    ///                                      &options).is_empty());
+    /// ```
+    pub fn transparent_inline_tags<I, S>(mut self, tags: I) -> DiffOptions
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        self.transparent_inline_tags = tags.into_iter().map(|s| s.as_ref().to_owned()).collect();
+        self
+    }
+
+    /// Skips the elements hidden with the `hidden` attribute or with `display: none` in their
+    /// `style` attribute (with their whole subtree), on both sides, so only what is displayed
+    /// is compared. The styles coming from stylesheets aren't taken into account.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::default().ignore_hidden(true);
+    /// assert!(get_differences_with_options("<p>a</p><p hidden>b</p>",
+    ///                                      "<p style=\"display: none\">c</p><p>a</p>",
+    ///                                      &options).is_empty());
+    /// ```
+    pub fn ignore_hidden(mut self, value: bool) -> DiffOptions {
+        self.ignore_hidden = value;
+        self
+    }
+
+    /// Compares the language inherited by the elements containing text instead of the `lang`
+    /// attributes themselves: `<html lang="en"><body><p>a</p>` and `<html><body><p lang="en">a`
+    /// match since the text of the `<p>` is in English in both cases.
+    ///
+    /// The `lang` attribute of the elements without text of their own is ignored, and the
+    /// elements with text get the `lang` of their nearest ancestor having one (compared
+    /// case-insensitively) or none.
+    pub fn compare_effective_lang(mut self, value: bool) -> DiffOptions {
+        self.compare_effective_lang = value;
+        self
+    }
+
+    /// Ignores the leading and trailing whitespace of the attribute values (`class=" a "` and
+    /// `class="a"` match).
+    pub fn trim_attribute_values(mut self, value: bool) -> DiffOptions {
+        self.trim_attribute_values = value;
+        self
+    }
+
+    /// Doesn't apply `normalize_whitespace` to the texts inside `<pre>` and `<textarea>`
+    /// elements, where the whitespace is displayed as written.
+    pub fn preserve_pre_whitespace(mut self, value: bool) -> DiffOptions {
+        self.preserve_pre_whitespace = value;
+        self
+    }
+
+    /// Calls `f` on the path of each difference found while walking the two trees and uses
+    /// what it returns as path instead (`ElementInformation::path`, the depth isn't changed).
+    /// It is called once per difference (the two elements of a difference have the same path),
+    /// after the difference has been found, so the paths used for the comparison itself (by
+    /// `DiffOptions::on_match` for example) aren't rewritten.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().path_rewriter(|path: &str| {
+    ///     path.trim_start_matches("/html[0]/body[0]").to_owned()
+    /// });
+    /// let differences = get_differences_with_options("<div><p>a</p></div>",
+    ///                                                "<div><p>b</p></div>", &options);
+    /// assert_eq!(differences[0].path(), "/div[0]/p[0]");
+    /// ```
+    pub fn path_rewriter<F>(mut self, f: F) -> DiffOptions
+        where F: Fn(&str) -> String + 'static
+    {
+        self.path_rewriter = Some(PathRewriter(Rc::new(f)));
+        self
+    }
+
+    /// Compares the `style` attributes as sets of declarations: the order of the declarations,
+    /// the case of the property names, the whitespace and the trailing `;` don't matter
+    /// (`color: red;font-size:12px` and `font-size: 12px; COLOR: red` match). When a property is
+    /// declared several times, only the last declaration is kept.
+    pub fn style_as_declarations(mut self, value: bool) -> DiffOptions {
+        self.style_as_declarations = value;
+        self
+    }
+
+    /// Compares the `class` attributes as sets of classes, so their order and repetitions
+    /// don't matter (`class="a b"` and `class="b a a"` match).
+    pub fn class_as_set(mut self, value: bool) -> DiffOptions {
+        self.class_as_set = value;
+        self
+    }
+
+    /// Sets the tolerance used to compare numbers (see `numeric_list_attributes`): two numbers
+    /// match if they differ by at most `tolerance`. It's `0.` by default.
+    pub fn numeric_tolerance(mut self, tolerance: f64) -> DiffOptions {
+        self.numeric_tolerance = tolerance;
+        self
+    }
+
+    /// Compares the values of the given attributes (like `viewBox` or `points`) as lists of
+    /// numbers separated by whitespace or commas: the separators don't matter and the numbers
+    /// are compared with `numeric_tolerance` (so `0,0 1.0,1` and `0 0 1 1` match). The tokens
+    /// which aren't numbers have to be equal.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().numeric_list_attributes(["viewBox"])
+    ///                                 .numeric_tolerance(0.01);
+    /// assert!(get_differences_with_options("<svg viewBox=\"0 0 100 33.333\"></svg>",
+    ///                                      "<svg viewBox=\"0,0,100,33.33\"></svg>",
+    ///                                      &options).is_empty());
+    /// ```
+    pub fn numeric_list_attributes<I, S>(mut self, attributes: I) -> DiffOptions
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        self.numeric_list_attributes = attributes.into_iter()
+                                                 .map(|s| s.as_ref().to_owned())
+                                                 .collect();
+        self
+    }
+
+    /// Removes the leading segments of the reported paths whose tag names are, in order, the
+    /// ones of `prefix` (`ElementInformation::path`, the depth isn't changed). The segments are
+    /// only removed when the two elements of a difference start with them, and the comparison
+    /// itself still sees the full paths. It's applied before `DiffOptions::path_rewriter`.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().trim_path_prefix(&["html", "body"]);
+    /// let differences = get_differences_with_options("<div><p>a</p></div>",
+    ///                                                "<div><p>b</p></div>", &options);
+    /// assert_eq!(differences[0].path(), "/div[0]/p[0]");
+    /// ```
+    pub fn trim_path_prefix(mut self, prefix: &[&str]) -> DiffOptions {
+        self.trim_path_prefix = prefix.iter().map(|name| (*name).to_owned()).collect();
+        self
+    }
+
+    /// Only reports the `Difference::NotPresent` and `Difference::NodeName` differences whose
+    /// elements have at least `min_subtree_size` descendants (on either side, not counting the
+    /// comments and the whitespace-only texts), to focus on the large structural changes. The
+    /// smaller subtrees are skipped as if they matched nothing, their children aren't compared.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().min_subtree_size(3);
+    /// let differences = get_differences_with_options(
+    ///     "<div><p>a</p><ul><li>b</li><li>c</li></ul></div><br>",
+    ///     "<div><p>a</p></div>",
+    ///     &options);
+    /// // The `<ul>` has 4 descendants, the `<br>` none.
+    /// assert_eq!(differences.len(), 1);
+    /// ```
+    pub fn min_subtree_size(mut self, min_subtree_size: usize) -> DiffOptions {
+        self.min_subtree_size = min_subtree_size;
+        self
+    }
+
+    /// Reports a `Difference::AttributeOrder` when two matching elements have the same
+    /// attributes written in a different order in the sources, for byte-reproduction checks.
+    /// The parsed trees don't keep the order of the attributes, so it's read again from the
+    /// start tags of the html content strings: it is only available to the functions given the
+    /// sources (like `get_differences_with_options`, not `get_differences_against`), outside of
+    /// the `unordered` mode. The ignored attributes aren't taken into account.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().strict_attribute_order(true);
+    /// let differences = get_differences_with_options("<p id=\"a\" class=\"b\">x</p>",
+    ///                                                "<p class=\"b\" id=\"a\">x</p>",
+    ///                                                &options);
+    /// assert!(differences[0].is_attribute_order());
+    /// ```
+    pub fn strict_attribute_order(mut self, value: bool) -> DiffOptions {
+        self.strict_attribute_order = value;
+        self
+    }
+
+    /// Drops the elements for which `f` returns `true` (with their subtrees) from both trees
+    /// before comparing them, like the comments.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions, NodeRef};
+    ///
+    /// let options = DiffOptions::new().ignore_elements(|node: &NodeRef| {
+    ///     node.as_element().map(|e| e.attributes.borrow().contains("data-random"))
+    ///                      .unwrap_or(false)
+    /// });
+    /// assert!(get_differences_with_options("<p>a</p><i data-random>1</i>", "<p>a</p>",
+    ///                                      &options).is_empty());
+    /// ```
+    pub fn ignore_elements<F>(mut self, f: F) -> DiffOptions
+        where F: Fn(&NodeRef) -> bool + 'static
+    {
+        self.ignore_elements = Some(ElementFilter(Rc::new(f)));
+        self
+    }
+
+    /// Compares the `<option>` children of a `<select>`, `<datalist>` or `<optgroup>` as a set
+    /// keyed by their value (their `value` attribute or, without it, their text with its
+    /// whitespace collapsed): reordering the options doesn't report anything, and the added,
+    /// removed or changed options are reported on their own. The other children are still
+    /// compared in order.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().options_as_set(true);
+    /// let differences = get_differences_with_options(
+    ///     "<select><option value=\"a\">A</option><option value=\"b\">B</option></select>",
+    ///     "<select><option value=\"b\">B</option><option value=\"a\">A</option></select>",
+    ///     &options);
+    /// assert!(differences.is_empty());
+    /// ```
+    pub fn options_as_set(mut self, value: bool) -> DiffOptions {
+        self.options_as_set = value;
+        self
+    }
+
+    /// Compares the texts longer than `max_len` characters (like big inline scripts or JSON
+    /// data) only by their length and a 64-bit hash of their content, without normalizing
+    /// them, and truncates them to `max_len` characters in the reported differences. The
+    /// shorter texts are compared as usual.
+    ///
+    /// Two different texts of the same length with the same hash are considered equal: the
+    /// hash (FNV-1a) makes it unlikely for unrelated texts, but it isn't a cryptographic hash
+    /// and collisions can be crafted.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().opaque_text_over(10);
+    /// let differences = get_differences_with_options("<script>var data = [1, 2];</script>",
+    ///                                                "<script>var data = [1, 3];</script>",
+    ///                                                &options);
+    /// assert_eq!(differences.len(), 1);
+    /// ```
+    pub fn opaque_text_over(mut self, max_len: usize) -> DiffOptions {
+        self.opaque_text_over = Some(max_len);
+        self
+    }
+
+    /// Parses the html content strings as XML-like markup, by putting them in the foreign
+    /// content of an `<svg>` element (removed from the paths, which start at the root elements
+    /// of the contents): there are no void elements (the text of an RSS `<link>` stays in it),
+    /// the self-closing tags (`<guid/>`) are honored, the CDATA sections are texts and no
+    /// `<html>`, `<head>` or `<body>` is implied. It isn't an XML parser though: the elements
+    /// on which the HTML parser leaves foreign content (like `<p>`, `<div>`, `<body>`, `<img>`
+    /// or `<br>`, so the content of an XHTML page) are still parsed as HTML, the HTML entities
+    /// are decoded and the names are lowercased (see `case_sensitive_names`).
+    ///
+    /// The reference given to `get_differences_against_with_options` has to be parsed with
+    /// `parse_document_with_options`.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().xml_syntax(true);
+    /// let differences = get_differences_with_options("<link>https://a.org</link>",
+    ///                                                "<link>https://b.org</link>", &options);
+    /// assert_eq!(differences.len(), 1);
+    /// assert_eq!(differences[0].path(), "/link[0]");
+    /// ```
+    pub fn xml_syntax(mut self, value: bool) -> DiffOptions {
+        self.xml_syntax = value;
+        self
+    }
+
+    /// Compares the tag and attribute names case-sensitively, as written in the sources (the
+    /// parser lowercases them): `<pubDate>` and `<pubdate>` are reported as a
+    /// `Difference::NodeName`, and attribute names with a different case as a
+    /// `Difference::NodeAttributes` (their keys are the names of the sources). Like
+    /// `strict_attribute_order`, it is only available to the functions given the sources.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().case_sensitive_names(true);
+    /// let differences = get_differences_with_options("<p>a</p>", "<P>a</P>", &options);
+    /// assert_eq!(differences.len(), 1);
+    /// assert!(differences[0].is_node_name());
+    /// ```
+    pub fn case_sensitive_names(mut self, value: bool) -> DiffOptions {
+        self.case_sensitive_names = value;
+        self
+    }
+
+    /// Only compares the elements: the texts (including the CDATA sections and the
+    /// `transparent_inline_tags` turned into texts) and the doctype are dropped from the
+    /// children before pairing them, so unlike with `ignore_text`, a text present on one side
+    /// only doesn't shift the alignment of the next elements. The attributes are still compared
+    /// with the other options.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().elements_only(true);
+    /// assert!(get_differences_with_options("<p>intro<b>a</b></p>", "<p><b>b</b>outro</p>",
+    ///                                      &options).is_empty());
+    /// assert_eq!(get_differences_with_options("<p><b class=\"x\"></b></p>", "<p><b></b></p>",
+    ///                                         &options).len(), 1);
+    /// ```
+    pub fn elements_only(mut self, value: bool) -> DiffOptions {
+        self.elements_only = value;
+        self
+    }
+
+    /// Compares the `<br>` elements as `\n` line breaks merged with the texts around them
+    /// (unless `collapse_text_nodes` is disabled), so `a<br>b` and `a\nb` match. Like the other
+    /// texts made only of whitespace, a `<br>` which isn't next to a text isn't compared.
+    /// The reported paths and contents are the ones of the original trees.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().br_as_newline(true);
+    /// assert!(get_differences_with_options("<p>Line 1<br>Line 2</p>", "<p>Line 1\nLine 2</p>",
+    ///                                      &options).is_empty());
+    /// ```
+    pub fn br_as_newline(mut self, value: bool) -> DiffOptions {
+        self.br_as_newline = value;
+        self
+    }
+
+    /// Only keeps the elements in the given state, for the documents containing several states
+    /// of a component: the elements whose `attribute` has another value than `value` are
+    /// dropped (with their subtrees) from both trees before comparing them. The elements
+    /// without `attribute` are kept, they are part of all the states.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions};
+    ///
+    /// let options = DiffOptions::new().select_state("data-state", "active");
+    /// assert!(get_differences_with_options(
+    ///     "<p>Menu</p><ul data-state=\"active\"><li>a</li></ul><ul data-state=\"idle\"></ul>",
+    ///     "<p>Menu</p><ul data-state=\"active\"><li>a</li></ul>",
+    ///     &options).is_empty());
+    /// ```
+    pub fn select_state(mut self, attribute: &str, value: &str) -> DiffOptions {
+        self.selected_state = Some((attribute.to_owned(), value.to_owned()));
+        self
+    }
+
+    // Whether `node` isn't dropped by `select_state`.
+    pub(crate) fn is_in_selected_state(&self, node: &NodeRef) -> bool {
+        let state = match self.selected_state {
+            Some(ref state) => state,
+            None => return true,
+        };
+        node.as_element()
+            .and_then(|e| e.attributes.borrow().get(state.0.as_str()).map(|v| v == state.1))
+            .unwrap_or(true)
+    }
+
+    /// Calls `f` with the number of nodes visited so far every time `every` more nodes were
+    /// visited by the walk (at least `1`), to show the progress of the comparison of big
+    /// documents. It doesn't change the differences found.
+    ///
+    /// The nodes which are visited are the children the walk goes through, so it doesn't reach
+    /// the nodes below a difference or between the matching subtrees of the `unordered` mode:
+    /// `count_compared_nodes` gives an upper bound to show a percentage.
+    ///
+    /// ```
+    /// use html_diff::{count_compared_nodes, get_differences_with_options, DiffOptions};
+    /// use std::cell::Cell;
+    /// use std::rc::Rc;
+    ///
+    /// let content = "<ul><li>1</li><li>2</li><li>3</li></ul>";
+    /// let total = count_compared_nodes(content, &DiffOptions::new());
+    /// let last = Rc::new(Cell::new(0));
+    /// let progress = last.clone();
+    /// let options = DiffOptions::new().progress(2, move |visited| progress.set(visited));
+    /// get_differences_with_options(content, content, &options);
+    /// assert!(last.get() > 0 && last.get() <= total);
+    /// ```
+    pub fn progress<F>(mut self, every: usize, f: F) -> DiffOptions
+        where F: FnMut(usize) + 'static
+    {
+        self.progress = Some(ProgressHook {
+            every: cmp::max(every, 1),
+            f: Rc::new(RefCell::new(f)),
+        });
+        self
+    }
+
+    /// Compares the elements with one of the given tags whose only child is an element with the
+    /// same name and attributes (so `<b><b>x</b></b>` and `<b>x</b>` match) as their child.
+    /// `NESTED_INLINE_TAGS` are the inline formatting elements which render the same when they
+    /// are nested. Nothing is collapsed by default.
+    ///
+    /// ```
+    /// use html_diff::{get_differences_with_options, DiffOptions, NESTED_INLINE_TAGS};
+    ///
+    /// let options = DiffOptions::new().collapse_nested_inline(NESTED_INLINE_TAGS);
+    /// assert!(get_differences_with_options("<p><em><em>a</em></em></p>", "<p><em>a</em></p>",
+    ///                                      &options).is_empty());
+    /// ```
+    pub fn collapse_nested_inline<I, S>(mut self, tags: I) -> DiffOptions
+        where I: IntoIterator<Item = S>,
+              S: AsRef<str>
+    {
+        self.collapse_nested_inline = tags.into_iter()
+                                          .map(|s| s.as_ref().to_ascii_lowercase())
+                                          .collect();
+        self
+    }
+}
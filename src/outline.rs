@@ -0,0 +1,140 @@
+use kuchiki::NodeRef;
+
+use attributes::collapse_whitespace;
+use {node_path, parse_document, Difference, ElementInformation};
+
+const HEADINGS: &[&str] = &["h1", "h2", "h3", "h4", "h5", "h6"];
+
+// A heading of the outline.
+struct Heading {
+    node: NodeRef,
+    level: usize,
+    text: String,
+}
+
+impl Heading {
+    fn parent_path(&self) -> Vec<String> {
+        let mut path = node_path(&self.node);
+        path.pop();
+        path
+    }
+
+    fn not_present(&self, left: bool) -> Difference {
+        let elem = Some(ElementInformation::from_node(&self.node, &self.parent_path()));
+        if left {
+            Difference::NotPresent { elem, opposite_elem: None }
+        } else {
+            Difference::NotPresent { elem: None, opposite_elem: elem }
+        }
+    }
+}
+
+// The headings of `document`, in document order.
+fn outline(document: &NodeRef) -> Vec<Heading> {
+    document.descendants().filter_map(|node| {
+        let name = node.as_element()?.name.local.clone();
+        let level = HEADINGS.iter().position(|h| *h == &*name)? + 1;
+        let text = collapse_whitespace(&node.text_contents());
+        Some(Heading { node, level, text })
+    }).collect()
+}
+
+// The pairs of positions of the headings with the same text, with the longest common
+// subsequence of the two outlines.
+fn matching_headings(headings1: &[Heading], headings2: &[Heading]) -> Vec<(usize, usize)> {
+    let (len1, len2) = (headings1.len(), headings2.len());
+    // `lengths[i][j]` is the length of the subsequence of `headings1[i..]` and `headings2[j..]`.
+    let mut lengths = vec![vec![0; len2 + 1]; len1 + 1];
+    for i in (0..len1).rev() {
+        for j in (0..len2).rev() {
+            lengths[i][j] = if headings1[i].text == headings2[j].text {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut pairs = Vec::new();
+    while i < len1 && j < len2 {
+        if headings1[i].text == headings2[j].text {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+// The differences between the headings which aren't matched by their text, between two
+// matching ones: the headings of the same level at the same position have a different text,
+// the other ones are missing.
+fn gap_differences(removed: &[Heading], added: &[Heading], differences: &mut Vec<Difference>) {
+    for i in 0..removed.len().max(added.len()) {
+        match (removed.get(i), added.get(i)) {
+            (Some(heading1), Some(heading2)) if heading1.level == heading2.level => {
+                differences.push(Difference::NodeText {
+                    elem: ElementInformation::from_path(&node_path(&heading1.node)),
+                    elem_text: heading1.text.clone(),
+                    opposite_elem: ElementInformation::from_path(&node_path(&heading2.node)),
+                    opposite_elem_text: heading2.text.clone(),
+                });
+            }
+            (heading1, heading2) => {
+                differences.extend(heading1.map(|h| h.not_present(true)));
+                differences.extend(heading2.map(|h| h.not_present(false)));
+            }
+        }
+    }
+}
+
+/// Returns the differences between the outlines of the two html content strings: the
+/// sequences of their `<h1>` to `<h6>` headings, with their level and their text (with its
+/// whitespace collapsed). The rest of the documents isn't compared.
+///
+/// The headings are matched by their text, in order. The differences are:
+///
+/// * a `Difference::NodeName` for a matching heading whose level changed (a demoted `<h2>`
+///   which became an `<h3>`),
+/// * a `Difference::NodeText` for a heading of the same level whose text changed, at the same
+///   place in the outline,
+/// * a `Difference::NotPresent` for a removed or added heading.
+///
+/// The paths are the ones of the headings in their documents.
+///
+/// ```
+/// use html_diff::heading_outline_differences;
+///
+/// let differences = heading_outline_differences(
+///     "<h1>Guide</h1><h2>Install</h2><p>a</p><h2>Usage</h2>",
+///     "<h1>Guide</h1><div><h3>Install</h3></div><p>b</p>");
+/// assert_eq!(differences.len(), 2);
+/// assert!(differences[0].is_node_name());
+/// assert!(differences[1].is_not_present());
+/// ```
+pub fn heading_outline_differences(content1: &str, content2: &str) -> Vec<Difference> {
+    // The documents must outlive the headings, the parents are only weak references.
+    let (document1, document2) = (parse_document(content1), parse_document(content2));
+    let (headings1, headings2) = (outline(&document1), outline(&document2));
+    let mut differences = Vec::new();
+    let (mut next1, mut next2) = (0, 0);
+    for (i, j) in matching_headings(&headings1, &headings2) {
+        gap_differences(&headings1[next1..i], &headings2[next2..j], &mut differences);
+        let (heading1, heading2) = (&headings1[i], &headings2[j]);
+        if heading1.level != heading2.level {
+            differences.push(Difference::NodeName {
+                elem: ElementInformation::from_node(&heading1.node, &heading1.parent_path()),
+                opposite_elem: ElementInformation::from_node(&heading2.node,
+                                                             &heading2.parent_path()),
+            });
+        }
+        next1 = i + 1;
+        next2 = j + 1;
+    }
+    gap_differences(&headings1[next1..], &headings2[next2..], &mut differences);
+    differences
+}
@@ -0,0 +1,161 @@
+use kuchiki::{Node, NodeRef};
+
+use std::collections::HashMap;
+
+use attribute_name;
+
+/// A start tag as written in the source.
+pub(crate) struct SourceTag {
+    pub(crate) name: String,
+    /// The attribute names in source order, without the duplicates (which the parser drops).
+    pub(crate) attributes: Vec<String>,
+    /// The byte offset of the `<` of the tag in the source.
+    pub(crate) offset: usize,
+}
+
+/// The start tags of the elements of a parsed document, as written in the source: the parser
+/// keeps neither the order of the attributes nor the case of the names.
+pub(crate) struct SourceTags(HashMap<*const Node, SourceTag>);
+
+impl SourceTags {
+    /// Matches the start tags of `content` with the elements of `tree` (its parsed form), by
+    /// name and attribute names.
+    ///
+    /// The elements created by the parser without a start tag of their own (like `<tbody>` or
+    /// the copies of misnested formatting elements) and the ones whose attributes were merged
+    /// from several tags aren't recorded.
+    pub(crate) fn new(content: &str, tree: &NodeRef) -> SourceTags {
+        let mut tags = start_tags(content).into_iter().map(Some).collect::<Vec<_>>();
+        let mut elements = HashMap::new();
+        let mut next_tag = 0;
+        for node in tree.inclusive_descendants() {
+            let elem = match node.as_element() {
+                Some(elem) => elem,
+                None => continue,
+            };
+            let attributes = elem.attributes.borrow();
+            let mut names = attributes.map.keys()
+                                          .map(|k| {
+                                              attribute_name(k.prefix.as_deref(), &k.local)
+                                                  .to_ascii_lowercase()
+                                          })
+                                          .collect::<Vec<_>>();
+            names.sort();
+            let found = tags[next_tag..].iter().position(|tag| {
+                tag.as_ref().map(|tag| {
+                    tag.name.eq_ignore_ascii_case(&elem.name.local) && {
+                        let mut tag_names = tag.attributes.iter()
+                                                          .map(|n| n.to_ascii_lowercase())
+                                                          .collect::<Vec<_>>();
+                        tag_names.sort();
+                        tag_names == names
+                    }
+                }).unwrap_or(false)
+            });
+            if let Some(pos) = found {
+                if let Some(tag) = tags[next_tag + pos].take() {
+                    elements.insert(&*node.0 as *const Node, tag);
+                }
+                next_tag += pos + 1;
+            }
+        }
+        SourceTags(elements)
+    }
+
+    /// Returns the start tag of `node`, if it's known.
+    pub(crate) fn get(&self, node: &NodeRef) -> Option<&SourceTag> {
+        self.0.get(&(&*node.0 as *const Node))
+    }
+}
+
+/// Returns the line and the column (in characters) of the byte `offset` of `content`, both
+/// starting at `1`.
+pub(crate) fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map(|pos| pos + 1).unwrap_or(0);
+    (before.matches('\n').count() + 1, before[line_start..].chars().count() + 1)
+}
+
+// The elements whose content isn't markup, so it mustn't be scanned for tags.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title", "xmp", "iframe",
+                                     "noembed", "noframes", "plaintext"];
+
+// Returns the start tags of `content`, in source order.
+fn start_tags(content: &str) -> Vec<SourceTag> {
+    let bytes = content.as_bytes();
+    let lowercase = content.to_ascii_lowercase();
+    let find = |from: usize, needle: &str| {
+        content[from..].find(needle).map(|pos| from + pos).unwrap_or(content.len())
+    };
+    let mut tags = Vec::new();
+    let mut i = 0;
+    while let Some(pos) = content[i..].find('<') {
+        let offset = i + pos;
+        i = offset + 1;
+        if content[i..].starts_with("!--") {
+            i = find(i, "-->");
+            continue
+        }
+        if content[i..].starts_with("![CDATA[") {
+            i = find(i, "]]>");
+            continue
+        }
+        if i >= bytes.len() || !bytes[i].is_ascii_alphabetic() {
+            if i < bytes.len() && (bytes[i] == b'!' || bytes[i] == b'?' || bytes[i] == b'/') {
+                i = find(i, ">");
+            }
+            continue
+        }
+        let end = |b: u8| b.is_ascii_whitespace() || b == b'/' || b == b'>';
+        let start = i;
+        while i < bytes.len() && !end(bytes[i]) {
+            i += 1;
+        }
+        let name = content[start..i].to_owned();
+        let mut attributes: Vec<String> = Vec::new();
+        loop {
+            while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+                i += 1;
+            }
+            if i >= bytes.len() || bytes[i] == b'>' {
+                break
+            }
+            let start = i;
+            // The name can start with `=`.
+            i += content[i..].chars().next().map_or(1, char::len_utf8);
+            while i < bytes.len() && !end(bytes[i]) && bytes[i] != b'=' {
+                i += 1;
+            }
+            let attribute = &content[start..i];
+            if !attributes.iter().any(|a| a.eq_ignore_ascii_case(attribute)) {
+                attributes.push(attribute.to_owned());
+            }
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if i >= bytes.len() || bytes[i] != b'=' {
+                continue
+            }
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if i < bytes.len() && (bytes[i] == b'"' || bytes[i] == b'\'') {
+                let quote = if bytes[i] == b'"' { "\"" } else { "'" };
+                i = (find(i + 1, quote) + 1).min(bytes.len());
+            } else {
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                    i += 1;
+                }
+            }
+        }
+        let lowercase_name = name.to_ascii_lowercase();
+        if RAW_TEXT_ELEMENTS.contains(&&*lowercase_name) {
+            i = lowercase[i..].find(&format!("</{}", lowercase_name))
+                              .map(|pos| i + pos)
+                              .unwrap_or(content.len());
+        }
+        tags.push(SourceTag { name, attributes, offset });
+    }
+    tags
+}
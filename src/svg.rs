@@ -0,0 +1,27 @@
+use kuchiki::NodeRef;
+
+const SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+
+// Returns the id referenced by the `href` (or `xlink:href`) attribute of an SVG `<use>`
+// element, if it references an element of the same document.
+pub fn use_reference(element: &NodeRef) -> Option<String> {
+    let elem = element.as_element()?;
+    if &*elem.name.local != "use" || &*elem.name.ns != SVG_NAMESPACE {
+        return None
+    }
+    let attributes = elem.attributes.borrow();
+    let href = attributes.map.iter().find(|&(k, _)| &*k.local == "href").map(|(_, v)| v)?;
+    if href.starts_with('#') && href.len() > 1 {
+        Some(href[1..].to_owned())
+    } else {
+        None
+    }
+}
+
+/// Returns the element with the given `id` in the document containing `node`.
+pub fn find_by_id(node: &NodeRef, id: &str) -> Option<NodeRef> {
+    let root = node.ancestors().last().unwrap_or_else(|| node.clone());
+    root.descendants().find(|n| {
+        n.as_element().map(|e| e.attributes.borrow().get("id") == Some(id)).unwrap_or(false)
+    })
+}
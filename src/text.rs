@@ -0,0 +1,69 @@
+use options::DiffOptions;
+
+/// Returns the text as compared with the given options.
+pub fn normalize(text: &str, options: &DiffOptions) -> String {
+    normalize_with(text, options.normalize_whitespace, options)
+}
+
+fn normalize_with(text: &str, normalize_whitespace: bool, options: &DiffOptions) -> String {
+    let mut text = if normalize_whitespace {
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    } else {
+        text.to_owned()
+    };
+    if options.treat_nbsp_as_space {
+        text = text.replace('\u{a0}', " ");
+    }
+    if options.case_insensitive_text {
+        text = text.to_lowercase();
+    }
+    text
+}
+
+/// Returns `true` if the two texts are considered equal.
+pub fn texts_match(text1: &str, text2: &str, options: &DiffOptions) -> bool {
+    texts_match_with(text1, text2, options.normalize_whitespace, options)
+}
+
+/// Same as `texts_match` for texts whose parent is at `path`, so
+/// `DiffOptions::preserve_pre_whitespace` can be applied.
+pub fn texts_match_at(text1: &str, text2: &str, path: &[String], options: &DiffOptions) -> bool {
+    let in_pre = path.iter().any(|s| s.starts_with("pre[") || s.starts_with("textarea["));
+    texts_match_with(text1, text2,
+                     options.normalize_whitespace && !(options.preserve_pre_whitespace && in_pre),
+                     options)
+}
+
+/// Returns `true` if `text` is only compared by length and hash (see
+/// `DiffOptions::opaque_text_over`).
+pub fn is_opaque(text: &str, options: &DiffOptions) -> bool {
+    match options.opaque_text_over {
+        // There are never more characters than bytes.
+        Some(max_len) => text.len() > max_len && text.chars().nth(max_len).is_some(),
+        None => false,
+    }
+}
+
+// FNV-1a.
+fn hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn texts_match_with(text1: &str, text2: &str, normalize_whitespace: bool,
+                    options: &DiffOptions) -> bool {
+    if options.ignore_text {
+        return true
+    }
+    if is_opaque(text1, options) || is_opaque(text2, options) {
+        return text1.len() == text2.len() && hash(text1) == hash(text2)
+    }
+    if text1 == text2 {
+        return true
+    }
+    let (text1, text2) = (normalize_with(text1, normalize_whitespace, options),
+                          normalize_with(text2, normalize_whitespace, options));
+    text1 == text2 ||
+    (text1.chars().count() < options.min_text_len && text2.chars().count() < options.min_text_len)
+}
@@ -0,0 +1,77 @@
+use kuchiki::NodeRef;
+
+use attributes;
+
+// The hosts (and paths) of the common analytics and advertising beacons.
+const TRACKING_URLS: &[&str] = &["google-analytics.com",
+                                 "googletagmanager.com",
+                                 "doubleclick.net",
+                                 "facebook.com/tr",
+                                 "connect.facebook.net",
+                                 "bat.bing.com",
+                                 "analytics.twitter.com",
+                                 "px.ads.linkedin.com",
+                                 "snap.licdn.com",
+                                 "static.hotjar.com",
+                                 "sb.scorecardresearch.com",
+                                 "mc.yandex.ru"];
+
+fn is_tracking_url(url: &str) -> bool {
+    let url = url.to_lowercase();
+    TRACKING_URLS.iter().any(|tracker| url.contains(tracker))
+}
+
+// Whether `value` (an attribute value or a `style` property) is a size of at most one pixel.
+fn is_pixel_size(value: Option<&str>) -> bool {
+    value.map(|v| v.trim().trim_end_matches("px").trim())
+         .and_then(|v| v.parse::<f64>().ok())
+         .map(|v| v <= 1.)
+         .unwrap_or(false)
+}
+
+/// Returns `true` if `node` looks like an analytics tracking element, the heuristics used by
+/// `DiffOptions::ignore_tracking`:
+///
+/// * an `<img>` of at most 1x1 pixel (in its `width` and `height` attributes or its `style`),
+/// * an `<img>`, `<iframe>` or `<script>` whose `src` is on a known tracking host (Google
+///   Analytics and Tag Manager, DoubleClick, the Facebook, Bing, Twitter and LinkedIn pixels,
+///   Hotjar, Scorecard Research and Yandex Metrica),
+/// * a `<script>` or `<noscript>` whose text mentions one of these hosts (the inline loaders).
+///
+/// ```
+/// use html_diff::{is_tracking_element, parse_document, NodeRef};
+///
+/// let document = parse_document("<img src=\"a.gif\" width=\"1\" height=\"1\">\
+///                                 <img src=\"b.png\">");
+/// let images = document.descendants()
+///                      .filter(|n| n.as_element().is_some())
+///                      .filter(|n| &*n.as_element().unwrap().name.local == "img")
+///                      .collect::<Vec<NodeRef>>();
+/// assert!(is_tracking_element(&images[0]));
+/// assert!(!is_tracking_element(&images[1]));
+/// ```
+pub fn is_tracking_element(node: &NodeRef) -> bool {
+    let elem = match node.as_element() {
+        Some(elem) => elem,
+        None => return false,
+    };
+    let attributes = elem.attributes.borrow();
+    let is_tracking_src = || attributes.get("src").map(is_tracking_url).unwrap_or(false);
+    match &*elem.name.local {
+        "img" => {
+            let size = |property: &str| {
+                attributes.get(property).map(|v| v.to_owned()).or_else(|| {
+                    attributes.get("style")
+                              .and_then(|style| attributes::style_property(style, property))
+                })
+            };
+            let is_pixel = is_pixel_size(size("width").as_deref()) &&
+                           is_pixel_size(size("height").as_deref());
+            is_pixel || is_tracking_src()
+        }
+        "iframe" => is_tracking_src(),
+        "script" => is_tracking_src() || is_tracking_url(&node.text_contents()),
+        "noscript" => is_tracking_url(&node.text_contents()),
+        _ => false,
+    }
+}
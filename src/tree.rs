@@ -0,0 +1,48 @@
+use Difference;
+
+/// Differences nested according to the document structure (see `build_difference_tree`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffTree {
+    /// The path segment of this node (`div[1]`), empty for the root.
+    pub segment: String,
+    /// The differences whose path ends at this node.
+    pub differences: Vec<Difference>,
+    /// The nodes below this one containing differences, in the order they were first found.
+    pub children: Vec<DiffTree>,
+}
+
+impl DiffTree {
+    fn new(segment: &str) -> DiffTree {
+        DiffTree {
+            segment: segment.to_owned(),
+            differences: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns the number of differences in this node and all the nodes below it.
+    pub fn count(&self) -> usize {
+        self.differences.len() + self.children.iter().map(|c| c.count()).sum::<usize>()
+    }
+}
+
+/// Nests the differences according to their paths: a difference whose path is
+/// `/html[0]/body[0]` ends up in the `body[0]` child of the `html[0]` child of the returned root.
+pub fn build_difference_tree(differences: &[Difference]) -> DiffTree {
+    let mut root = DiffTree::new("");
+    for diff in differences {
+        let mut node = &mut root;
+        for segment in diff.elem_information().path_segments() {
+            let pos = match node.children.iter().position(|c| c.segment == segment) {
+                Some(pos) => pos,
+                None => {
+                    node.children.push(DiffTree::new(segment));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[pos];
+        }
+        node.differences.push(diff.clone());
+    }
+    root
+}
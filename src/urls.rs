@@ -0,0 +1,122 @@
+// The attributes containing one URL.
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "action", "formaction", "poster", "cite",
+                                  "data", "background", "manifest"];
+
+/// Returns `true` if the value of the `name` attribute is a URL.
+pub fn is_url_attribute(name: &str) -> bool {
+    URL_ATTRIBUTES.contains(&name)
+}
+
+// The components of a URI reference (RFC 3986, section 4.1).
+struct Reference<'a> {
+    scheme: Option<&'a str>,
+    authority: Option<&'a str>,
+    path: &'a str,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+fn split_once(s: &str, c: char) -> (&str, Option<&str>) {
+    match s.find(c) {
+        Some(pos) => (&s[..pos], Some(&s[pos + 1..])),
+        None => (s, None),
+    }
+}
+
+fn is_scheme(s: &str) -> bool {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+}
+
+impl<'a> Reference<'a> {
+    fn parse(s: &'a str) -> Reference<'a> {
+        let (s, fragment) = split_once(s, '#');
+        let (s, query) = split_once(s, '?');
+        let (scheme, s) = match s.find(':') {
+            Some(pos) if is_scheme(&s[..pos]) => (Some(&s[..pos]), &s[pos + 1..]),
+            _ => (None, s),
+        };
+        let (authority, path) = match s.strip_prefix("//") {
+            Some(s) => {
+                let end = s.find('/').unwrap_or(s.len());
+                (Some(&s[..end]), &s[end..])
+            }
+            None => (None, s),
+        };
+        Reference { scheme, authority, path, query, fragment }
+    }
+}
+
+// RFC 3986, section 5.2.4.
+fn remove_dot_segments(path: &str) -> String {
+    let mut output: Vec<&str> = Vec::new();
+    let segments = path.split('/').collect::<Vec<_>>();
+    for (pos, segment) in segments.iter().enumerate() {
+        let is_last = pos + 1 == segments.len();
+        match *segment {
+            "." => {
+                if is_last {
+                    output.push("");
+                }
+            }
+            ".." => {
+                if output.len() > 1 {
+                    output.pop();
+                }
+                if is_last {
+                    output.push("");
+                }
+            }
+            segment => output.push(segment),
+        }
+    }
+    output.join("/")
+}
+
+// RFC 3986, section 5.2.3.
+fn merge(base: &Reference, path: &str) -> String {
+    if base.authority.is_some() && base.path.is_empty() {
+        return format!("/{}", path)
+    }
+    match base.path.rfind('/') {
+        Some(pos) => format!("{}{}", &base.path[..pos + 1], path),
+        None => path.to_owned(),
+    }
+}
+
+/// Resolves `value` against the absolute URL `base` (RFC 3986, section 5.2.2). Returns `None`
+/// if `base` isn't an absolute URL.
+pub fn resolve(base: &str, value: &str) -> Option<String> {
+    let base = Reference::parse(base.trim());
+    let reference = Reference::parse(value.trim());
+    let scheme = base.scheme?;
+    let (scheme, authority, path, query) = if reference.scheme.is_some() {
+        (reference.scheme.unwrap_or(scheme), reference.authority,
+         remove_dot_segments(reference.path), reference.query)
+    } else if reference.authority.is_some() {
+        (scheme, reference.authority, remove_dot_segments(reference.path), reference.query)
+    } else if reference.path.is_empty() {
+        (scheme, base.authority, base.path.to_owned(), reference.query.or(base.query))
+    } else if reference.path.starts_with('/') {
+        (scheme, base.authority, remove_dot_segments(reference.path), reference.query)
+    } else {
+        (scheme, base.authority, remove_dot_segments(&merge(&base, reference.path)),
+         reference.query)
+    };
+    let mut url = format!("{}:", scheme);
+    if let Some(authority) = authority {
+        url.push_str(&format!("//{}", authority));
+    }
+    url.push_str(&path);
+    if let Some(query) = query {
+        url.push_str(&format!("?{}", query));
+    }
+    if let Some(fragment) = reference.fragment {
+        url.push_str(&format!("#{}", fragment));
+    }
+    Some(url)
+}
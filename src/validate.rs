@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use {node_path, parse_document};
+
+/// A validity error found in a content by `validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Validity {
+    /// Several elements have the same `id`.
+    DuplicateId {
+        id: String,
+        /// The paths of the elements having this `id` (including their own segment, like
+        /// `/html[0]/body[0]/p[1]`), in document order.
+        paths: Vec<String>,
+    },
+}
+
+impl fmt::Display for Validity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Validity::DuplicateId { ref id, ref paths } => {
+                write!(f, "the id {:?} is used by {} elements: {}", id, paths.len(),
+                       paths.join(", "))
+            }
+        }
+    }
+}
+
+/// Returns the validity errors of the html content string (the `id`s used by several elements
+/// for now), in the order of their first occurrence.
+///
+/// They aren't differences, so they are never returned by the comparison functions.
+///
+/// ```
+/// use html_diff::{validate, Validity};
+///
+/// assert!(validate("<p id=\"a\"></p><p id=\"b\"></p>").is_empty());
+/// assert_eq!(validate("<p id=\"a\"></p><p id=\"a\"></p>"),
+///            vec![Validity::DuplicateId {
+///                id: "a".to_owned(),
+///                paths: vec!["/html[0]/body[0]/p[0]".to_owned(),
+///                            "/html[0]/body[0]/p[1]".to_owned()],
+///            }]);
+/// ```
+pub fn validate(content: &str) -> Vec<Validity> {
+    let document = parse_document(content);
+    let mut ids: Vec<(String, Vec<String>)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for node in document.descendants() {
+        let id = match node.as_element().and_then(|e| e.attributes.borrow().get("id")
+                                                                    .map(|id| id.to_owned())) {
+            Some(id) => id,
+            None => continue,
+        };
+        let path = node_path(&node).join("/");
+        match positions.get(&id) {
+            Some(&pos) => ids[pos].1.push(path),
+            None => {
+                positions.insert(id.clone(), ids.len());
+                ids.push((id, vec![path]));
+            }
+        }
+    }
+    ids.into_iter()
+       .filter(|id| id.1.len() > 1)
+       .map(|(id, paths)| Validity::DuplicateId { id, paths })
+       .collect()
+}
@@ -0,0 +1,79 @@
+use std::fmt;
+
+/// Something in the compared contents which makes the differences unlikely to be useful.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffWarning {
+    /// One of the contents is a full document (starting with `<!DOCTYPE>` or `<html>`) while
+    /// the other one is a fragment: the differences will mostly be about the `<html>`, `<head>`
+    /// and `<body>` elements the parser adds around the fragment.
+    FragmentComparedToDocument {
+        /// `true` if the first content is the fragment.
+        first_is_fragment: bool,
+    },
+}
+
+impl fmt::Display for DiffWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DiffWarning::FragmentComparedToDocument { first_is_fragment } => {
+                let (fragment, document) = if first_is_fragment {
+                    ("first", "second")
+                } else {
+                    ("second", "first")
+                };
+                write!(f, "the {} content is a fragment while the {} one is a full document, \
+                           compare the fragment with the matching part of the document instead",
+                       fragment, document)
+            }
+        }
+    }
+}
+
+// Skips leading whitespace and comments, then looks for a doctype or an `<html>` tag.
+fn is_full_document(content: &str) -> bool {
+    let mut content = content.trim_start();
+    while content.starts_with("<!--") {
+        content = match content.find("-->") {
+            Some(pos) => content[pos + 3..].trim_start(),
+            None => return false,
+        };
+    }
+    let start = content.chars().take(9).collect::<String>().to_lowercase();
+    start.starts_with("<!doctype") || start.starts_with("<html")
+}
+
+// Returns `true` if `content` contains a `<name>` opening tag (with or without attributes).
+fn has_tag(content: &str, name: &str) -> bool {
+    let tag = format!("<{}", name);
+    content.match_indices(&tag).any(|(pos, _)| {
+        match content[pos + tag.len()..].chars().next() {
+            Some(c) => c == '>' || c == '/' || c.is_whitespace(),
+            None => false,
+        }
+    })
+}
+
+/// Returns `true` if `content` has no doctype and no explicit `<html>`, `<head>` or `<body>`
+/// tag.
+pub fn is_fragment(content: &str) -> bool {
+    if is_full_document(content) {
+        return false
+    }
+    let content = content.to_lowercase();
+    !has_tag(&content, "html") && !has_tag(&content, "head") && !has_tag(&content, "body")
+}
+
+/// Returns the warnings about comparing `content1` with `content2`.
+pub fn get_warnings(content1: &str, content2: &str) -> Vec<DiffWarning> {
+    let mut warnings = Vec::new();
+    match (is_full_document(content1), is_full_document(content2)) {
+        (false, true) if !content1.trim().is_empty() => {
+            warnings.push(DiffWarning::FragmentComparedToDocument { first_is_fragment: true });
+        }
+        (true, false) if !content2.trim().is_empty() => {
+            warnings.push(DiffWarning::FragmentComparedToDocument { first_is_fragment: false });
+        }
+        _ => {}
+    }
+    warnings
+}
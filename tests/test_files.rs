@@ -25,7 +25,7 @@ fn run_test(path: &str) -> bool {
     let differences = html_diff::get_differences(&original, &compare);
     let mut out = Vec::new();
     for diff in differences {
-        out.push(format!("=> {}", diff.to_string()));
+        out.push(format!("=> {}", diff));
     }
     let out = out.join("\n");
     if out != expected_out {